@@ -0,0 +1,389 @@
+use std::collections::VecDeque;
+use std::io;
+use std::ops::{ControlFlow, Range};
+use std::vec::Drain;
+
+use crate::internals::State;
+use crate::{Error, JsonChecker, JsonType};
+
+/// The structural events recognized by the `Scanner`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Event {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key,
+    Scalar(JsonType),
+}
+
+/// An event along with the position of the bytes that produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub event: Event,
+    /// The range of the raw bytes of this token, quotes included.
+    pub span: Range<usize>,
+    /// The number of containers enclosing this token.
+    pub depth: usize,
+    /// The raw bytes of the keys and scalars, only filled when capturing.
+    pub bytes: Vec<u8>,
+}
+
+/// Drives a `JsonChecker` byte by byte and recognizes
+/// the structural tokens while validating them.
+pub(crate) struct Scanner {
+    checker: JsonChecker<()>,
+    depth: usize,
+    capture: bool,
+    in_token: bool,
+    token_start: usize,
+    buffer: Vec<u8>,
+    tokens: Vec<Token>,
+}
+
+impl Scanner {
+    /// Construct a `Scanner`, if `capture` is `true` the raw bytes
+    /// of the keys and scalars are stored in the tokens.
+    pub fn new(capture: bool) -> Scanner {
+        Scanner::with_checker(JsonChecker::new(()), capture)
+    }
+
+    /// Construct a `Scanner` that uses an already configured `JsonChecker`.
+    pub fn with_checker(checker: JsonChecker<()>, capture: bool) -> Scanner {
+        Scanner {
+            checker,
+            depth: 0,
+            capture,
+            in_token: false,
+            token_start: 0,
+            buffer: Vec::new(),
+            tokens: Vec::new(),
+        }
+    }
+
+    /// The current state of the underlying automaton.
+    pub fn state(&self) -> State {
+        self.checker.state
+    }
+
+    /// Process a single byte, the recognized tokens can be retrieved with `Scanner::drain`.
+    pub fn feed(&mut self, byte: u8) -> Result<(), Error> {
+        let prev = self.checker.state;
+        let offset = self.checker.offset;
+        self.checker.next_byte(byte)?;
+
+        // A number ends on the first byte that is not part of it.
+        if is_number(prev) && !is_number(self.checker.state) {
+            self.end_token(Event::Scalar(JsonType::Number), offset);
+        }
+
+        if self.in_token && self.capture {
+            self.buffer.push(byte);
+        }
+
+        match prev {
+            State::Go | State::Va | State::Ar => match byte {
+                b'{' => self.start_container(Event::StartObject, offset),
+                b'[' => self.start_container(Event::StartArray, offset),
+                b']' => self.end_container(Event::EndArray, offset),
+                b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n' => self.start_token(byte, offset),
+                _ => (),
+            },
+            State::Ob | State::Ke => match byte {
+                b'"' => self.start_token(byte, offset),
+                b'}' => self.end_container(Event::EndObject, offset),
+                _ => (),
+            },
+            State::St if byte == b'"' => {
+                let event = match self.checker.state {
+                    State::Co => Event::Key,
+                    _ => Event::Scalar(JsonType::String),
+                };
+                self.end_token(event, offset + 1);
+            },
+            State::T3 | State::F4 => self.end_token(Event::Scalar(JsonType::Bool), offset + 1),
+            State::N3 => self.end_token(Event::Scalar(JsonType::Null), offset + 1),
+            state if state == State::Ok || is_number(state) => match byte {
+                b'}' => self.end_container(Event::EndObject, offset),
+                b']' => self.end_container(Event::EndArray, offset),
+                _ => (),
+            },
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve the tokens recognized since the last call.
+    pub fn drain(&mut self) -> Drain<'_, Token> {
+        self.tokens.drain(..)
+    }
+
+    /// Recognize a number that ends the document, must be called
+    /// before the last `Scanner::drain` and `Scanner::finish` calls.
+    pub fn end_of_input(&mut self) {
+        if is_number(self.checker.state) {
+            let offset = self.checker.offset;
+            self.end_token(Event::Scalar(JsonType::Number), offset);
+        }
+    }
+
+    /// Consumes the `Scanner` and returns the JSON type guessed.
+    pub fn finish(self) -> Result<JsonType, Error> {
+        self.checker.finish()
+    }
+
+    fn start_container(&mut self, event: Event, offset: usize) {
+        self.push_token(event, offset..offset + 1, Vec::new());
+        self.depth += 1;
+    }
+
+    fn end_container(&mut self, event: Event, offset: usize) {
+        self.depth -= 1;
+        self.push_token(event, offset..offset + 1, Vec::new());
+    }
+
+    fn start_token(&mut self, byte: u8, offset: usize) {
+        self.in_token = true;
+        self.token_start = offset;
+        if self.capture {
+            self.buffer.push(byte);
+        }
+    }
+
+    fn end_token(&mut self, event: Event, end: usize) {
+        self.in_token = false;
+        let bytes = std::mem::take(&mut self.buffer);
+        self.push_token(event, self.token_start..end, bytes);
+    }
+
+    fn push_token(&mut self, event: Event, span: Range<usize>, bytes: Vec<u8>) {
+        let depth = self.depth;
+        self.tokens.push(Token { event, span, depth, bytes });
+    }
+}
+
+/// An event produced by the `JsonEvents` iterator.
+///
+/// The keys and scalars are given with their raw bytes, quotes and escapes included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonEvent {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(Vec<u8>),
+    Scalar(JsonType, Vec<u8>),
+}
+
+impl From<Token> for JsonEvent {
+    fn from(token: Token) -> JsonEvent {
+        match token.event {
+            Event::StartObject => JsonEvent::StartObject,
+            Event::EndObject => JsonEvent::EndObject,
+            Event::StartArray => JsonEvent::StartArray,
+            Event::EndArray => JsonEvent::EndArray,
+            Event::Key => JsonEvent::Key(token.bytes),
+            Event::Scalar(json_type) => JsonEvent::Scalar(json_type, token.bytes),
+        }
+    }
+}
+
+/// An iterator that validates the JSON from a reader and yields its events one by one.
+///
+/// The reader is only read when the buffered bytes are exhausted. The iterator ends
+/// once the whole document has been validated or after the first error has been returned.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::{JsonEvents, JsonEvent, JsonType};
+///
+/// let text = r#"{"id": 5}"#;
+/// let events = JsonEvents::new(text.as_bytes()).collect::<Result<Vec<_>, _>>()?;
+///
+/// assert_eq!(events, vec![
+///     JsonEvent::StartObject,
+///     JsonEvent::Key(br#""id""#.to_vec()),
+///     JsonEvent::Scalar(JsonType::Number, b"5".to_vec()),
+///     JsonEvent::EndObject,
+/// ]);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub struct JsonEvents<R> {
+    reader: R,
+    scanner: Option<Scanner>,
+    buffer: Box<[u8]>,
+    pos: usize,
+    len: usize,
+    events: VecDeque<JsonEvent>,
+    error: Option<io::Error>,
+}
+
+impl<R> JsonEvents<R> {
+    /// Construct a `JsonEvents` iterator that reads from the given reader.
+    pub fn new(reader: R) -> JsonEvents<R> {
+        JsonEvents {
+            reader,
+            scanner: Some(Scanner::new(true)),
+            buffer: vec![0; 8192].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            events: VecDeque::new(),
+            error: None,
+        }
+    }
+}
+
+impl<R: io::Read> Iterator for JsonEvents<R> {
+    type Item = io::Result<JsonEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(Ok(event));
+            }
+
+            // The error of the end of the document is returned after the last events.
+            if let Some(error) = self.error.take() {
+                return Some(Err(error));
+            }
+
+            let scanner = self.scanner.as_mut()?;
+
+            if self.pos == self.len {
+                match self.reader.read(&mut self.buffer) {
+                    Ok(0) => {
+                        scanner.end_of_input();
+                        self.events.extend(scanner.drain().map(JsonEvent::from));
+                        if let Some(Err(error)) = self.scanner.take().map(Scanner::finish) {
+                            self.error = Some(error.into());
+                        }
+                    },
+                    Ok(len) => {
+                        self.pos = 0;
+                        self.len = len;
+                    },
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                    Err(e) => {
+                        self.scanner = None;
+                        return Some(Err(e));
+                    },
+                }
+                continue;
+            }
+
+            while self.pos < self.len && self.events.is_empty() {
+                let byte = self.buffer[self.pos];
+                self.pos += 1;
+                if let Err(error) = scanner.feed(byte) {
+                    self.scanner = None;
+                    return Some(Err(error.into()));
+                }
+                self.events.extend(scanner.drain().map(JsonEvent::from));
+            }
+        }
+    }
+}
+
+/// Returns `true` if the automaton is reading a number in this state.
+fn is_number(state: State) -> bool {
+    matches!(
+        state,
+        State::Mi | State::Ze | State::In | State::Fr | State::Fs | State::E1 | State::E2 | State::E3
+    )
+}
+
+/// Read all of the bytes from the reader and call `f` for each of them.
+pub(crate) fn for_each_byte<R, F>(mut reader: R, mut f: F) -> io::Result<()>
+where
+    R: io::Read,
+    F: FnMut(u8) -> Result<(), Error>,
+{
+    let mut buf = [0; 8192];
+
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(len) => len,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        for byte in &buf[..len] {
+            f(*byte)?;
+        }
+    }
+}
+
+/// Validate the bytes from the reader and call `f` for every token recognized,
+/// the raw bytes of the keys and scalars are only available when capturing.
+pub(crate) fn scan_reader<R, F>(reader: R, capture: bool, mut f: F) -> io::Result<JsonType>
+where
+    R: io::Read,
+    F: FnMut(Token),
+{
+    let mut scanner = Scanner::new(capture);
+
+    for_each_byte(reader, |byte| {
+        scanner.feed(byte)?;
+        scanner.drain().for_each(&mut f);
+        Ok(())
+    })?;
+
+    scanner.end_of_input();
+    scanner.drain().for_each(&mut f);
+    Ok(scanner.finish()?)
+}
+
+/// Validate the bytes from the reader with the scanner and call `f` for every token
+/// recognized, the reader is no more read as soon as `f` breaks and `None` is returned.
+pub(crate) fn try_scan_reader<R, F>(mut reader: R, mut scanner: Scanner, mut f: F) -> io::Result<Option<JsonType>>
+where
+    R: io::Read,
+    F: FnMut(Token) -> ControlFlow<()>,
+{
+    let mut buf = [0; 8192];
+
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        for byte in &buf[..len] {
+            scanner.feed(*byte)?;
+            if scanner.drain().try_for_each(&mut f).is_break() {
+                return Ok(None);
+            }
+        }
+    }
+
+    scanner.end_of_input();
+    if scanner.drain().try_for_each(&mut f).is_break() {
+        return Ok(None);
+    }
+
+    Ok(Some(scanner.finish()?))
+}
+
+/// Validate the bytes and call `f` for every token recognized.
+pub(crate) fn scan_bytes<F>(bytes: &[u8], mut f: F) -> Result<JsonType, Error>
+where
+    F: FnMut(Token),
+{
+    let mut scanner = Scanner::new(false);
+
+    for byte in bytes {
+        scanner.feed(*byte)?;
+        scanner.drain().for_each(&mut f);
+    }
+
+    scanner.end_of_input();
+    scanner.drain().for_each(&mut f);
+    scanner.finish()
+}
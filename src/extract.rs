@@ -0,0 +1,342 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::ops::{ControlFlow, Range};
+
+use crate::events::{scan_bytes, scan_reader, try_scan_reader, Event, Scanner, Token};
+use crate::path::PathTracker;
+use crate::{Error, JsonChecker, JsonType, Position};
+
+/// Validate the JSON bytes and returns the raw bytes and the type
+/// of the value of the first member of the root object.
+///
+/// Returns `None` if the root value is not an object or is an empty object.
+/// This is useful to quickly retrieve the discriminant of a tagged object.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::{first_field_value, JsonType};
+///
+/// let text = r#"{"type": "user", "id": 5}"#;
+/// let value = first_field_value(text.as_bytes())?;
+/// assert_eq!(value, Some((br#""user""#.to_vec(), JsonType::String)));
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn first_field_value(bytes: &[u8]) -> Result<Option<(Vec<u8>, JsonType)>, Error> {
+    let mut key_seen = false;
+    let mut container_start = None;
+    let mut value = None;
+
+    scan_bytes(bytes, |token| {
+        // We are only interested in the tokens of the root object.
+        if value.is_some() || token.depth != 1 {
+            return;
+        }
+
+        match token.event {
+            Event::Key => key_seen = true,
+            Event::Scalar(json_type) if key_seen => value = Some((token.span, json_type)),
+            Event::StartObject if key_seen => container_start = Some(token.span.start),
+            Event::StartArray if key_seen => container_start = Some(token.span.start),
+            Event::EndObject | Event::EndArray => if let Some(start) = container_start {
+                let json_type = match token.event {
+                    Event::EndObject => JsonType::Object,
+                    _ => JsonType::Array,
+                };
+                value = Some((start..token.span.end, json_type));
+            },
+            _ => (),
+        }
+    })?;
+
+    Ok(value.map(|(span, json_type)| (bytes[span].to_vec(), json_type)))
+}
+
+/// Validate the JSON from the reader and returns `true` if a scalar
+/// element of the root array appears more than once.
+///
+/// Scalars are compared by their raw bytes, `1` and `1.0` are therefore different.
+/// The objects and arrays elements of the root array are ignored.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::has_duplicate_elements;
+///
+/// assert!(has_duplicate_elements(r#"["a", 2, "a"]"#.as_bytes())?);
+/// assert!(!has_duplicate_elements(r#"[1, 2, [1]]"#.as_bytes())?);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn has_duplicate_elements<R: io::Read>(reader: R) -> io::Result<bool> {
+    let mut in_root_array = false;
+    let mut elements = HashSet::new();
+    let mut duplicate = false;
+
+    scan_reader(reader, true, |token| {
+        match token.event {
+            Event::StartArray if token.depth == 0 => in_root_array = true,
+            Event::Scalar(_) if in_root_array && token.depth == 1 && !duplicate => {
+                duplicate = !elements.insert(token.bytes);
+            },
+            _ => (),
+        }
+    })?;
+
+    Ok(duplicate)
+}
+
+/// Validate the JSON from the reader and returns the ranges of the content
+/// of all of the strings, keys included, the quotes are excluded.
+///
+/// These ranges can be used to mask the strings of the original bytes.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::string_spans;
+///
+/// let text = r#"{"a": "secret"}"#;
+/// let spans = string_spans(text.as_bytes())?;
+/// assert_eq!(spans, vec![2..3, 7..13]);
+/// assert_eq!(&text[spans[1].clone()], "secret");
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn string_spans<R: io::Read>(reader: R) -> io::Result<Vec<Range<usize>>> {
+    let mut spans = Vec::new();
+
+    scan_reader(reader, false, |token| {
+        if let Event::Key | Event::Scalar(JsonType::String) = token.event {
+            spans.push(token.span.start + 1..token.span.end - 1);
+        }
+    })?;
+
+    Ok(spans)
+}
+
+/// Validate the JSON bytes and returns the type of the root value along with
+/// the offset of the byte just after it, the trailing whitespaces excluded.
+///
+/// This is the offset at which the bytes can be cut when multiple values are concatenated.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::{value_end_offset, JsonType};
+///
+/// let text = "  [1, 2, 3]\n";
+/// assert_eq!(value_end_offset(text.as_bytes())?, (JsonType::Array, 11));
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn value_end_offset(bytes: &[u8]) -> Result<(JsonType, usize), Error> {
+    let mut end = 0;
+
+    let json_type = scan_bytes(bytes, |token| {
+        if token.depth == 0 {
+            if let Event::Scalar(_) | Event::EndObject | Event::EndArray = token.event {
+                end = token.span.end;
+            }
+        }
+    })?;
+
+    Ok((json_type, end))
+}
+
+/// Returns the types of the first `k` elements of the root array.
+///
+/// The reader is no more read and validated once `k` elements have been seen,
+/// fewer types are returned when the array contains fewer elements.
+/// An `Error::UnexpectedRootType` is returned if the root value is not an array.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::{array_preview, JsonType};
+///
+/// let text = r#"[1, {"a": [2]}, this is not validated"#;
+/// let types = array_preview(text.as_bytes(), 2)?;
+/// assert_eq!(types, vec![JsonType::Number, JsonType::Object]);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn array_preview<R: io::Read>(reader: R, k: usize) -> io::Result<Vec<JsonType>> {
+    let checker = JsonChecker::new(()).allowed_root_types(&[JsonType::Array]);
+    let scanner = Scanner::with_checker(checker, false);
+    let mut types = Vec::new();
+
+    if k == 0 {
+        return Ok(types);
+    }
+
+    try_scan_reader(reader, scanner, |token| {
+        types.extend(element_type(token));
+        if types.len() == k { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    })?;
+
+    Ok(types)
+}
+
+/// Returns the type of the elements of the root container that starts with this token.
+fn element_type(token: Token) -> Option<JsonType> {
+    match token.event {
+        _ if token.depth != 1 => None,
+        Event::Scalar(json_type) => Some(json_type),
+        Event::StartObject => Some(JsonType::Object),
+        Event::StartArray => Some(JsonType::Array),
+        _ => None,
+    }
+}
+
+/// Validate the JSON bytes and returns the type of the root value along with the
+/// positions of its first and last bytes, the surrounding whitespaces excluded.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::{validate_span, JsonType};
+///
+/// let text = "{\n  \"a\": 1\n}\n";
+/// let (json_type, start, end) = validate_span(text.as_bytes())?;
+/// assert_eq!(json_type, JsonType::Object);
+/// assert_eq!((start.line, start.column), (1, 1));
+/// assert_eq!((end.line, end.column), (3, 1));
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn validate_span(bytes: &[u8]) -> Result<(JsonType, Position, Position), Error> {
+    let mut start = None;
+    let mut end = 0;
+
+    let json_type = scan_bytes(bytes, |token| {
+        if token.depth == 0 {
+            start.get_or_insert(token.span.start);
+            end = token.span.end;
+        }
+    })?;
+
+    // A valid document always contains a root value.
+    let start = start.expect("BUG: the root value must have been found");
+    Ok((json_type, Position::at(bytes, start), Position::at(bytes, end - 1)))
+}
+
+/// Validate the JSON from the reader and returns the JSON pointer of the first
+/// scalar value whose raw bytes are equal to the needle, quotes included for strings.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::find_value;
+///
+/// let text = r#"{"users": [{"name": "kero"}, {"name": "clem"}]}"#;
+/// let path = find_value(text.as_bytes(), br#""clem""#)?;
+/// assert_eq!(path.as_deref(), Some("/users/1/name"));
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn find_value<R: io::Read>(reader: R, needle: &[u8]) -> io::Result<Option<String>> {
+    let mut tracker = PathTracker::default();
+    let mut path = None;
+
+    scan_reader(reader, true, |token| {
+        if path.is_none() {
+            tracker.token(&token);
+            if let Event::Scalar(_) = token.event {
+                if token.bytes == needle {
+                    path = Some(tracker.pointer());
+                }
+            }
+        }
+    })?;
+
+    Ok(path)
+}
+
+/// Validate the JSON from the reader and count, for a root array of objects,
+/// the number of `null` values of each key, keys are given without their quotes.
+///
+/// This is useful to compute the null rates of the columns of a dataset.
+/// An empty map is returned if the root value is not an array.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::null_counts_by_key;
+///
+/// let text = r#"[{"id": 1, "name": null}, {"id": 2, "name": null}]"#;
+/// let counts = null_counts_by_key(text.as_bytes())?;
+/// assert_eq!(counts.get(&b"name"[..]), Some(&2));
+/// assert_eq!(counts.get(&b"id"[..]), None);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn null_counts_by_key<R: io::Read>(reader: R) -> io::Result<HashMap<Vec<u8>, usize>> {
+    let mut in_root_array = false;
+    let mut key = None;
+    let mut counts = HashMap::new();
+
+    scan_reader(reader, true, |token| {
+        match (token.event, token.depth) {
+            (Event::StartArray, 0) => in_root_array = true,
+            (Event::StartObject, 1) | (Event::StartArray, 1) => key = None,
+            (Event::Key, 2) => key = Some(token.bytes),
+            (Event::Scalar(JsonType::Null), 2) if in_root_array => {
+                if let Some(key) = &key {
+                    let key = key[1..key.len() - 1].to_vec();
+                    *counts.entry(key).or_insert(0) += 1;
+                }
+            },
+            _ => (),
+        }
+    })?;
+
+    Ok(counts)
+}
+
+/// Validate the JSON from the reader and call `f` for every scalar value with its
+/// JSON pointer, its raw bytes, quotes included for strings, and its type.
+///
+/// The values are given in the order of the document, the empty arrays and objects
+/// are not given.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::flatten;
+///
+/// let text = r#"{"user": {"name": "kero", "tags": ["a"]}}"#;
+/// let mut pairs = Vec::new();
+/// flatten(text.as_bytes(), |pointer, bytes, _| pairs.push((pointer, bytes.to_vec())))?;
+///
+/// assert_eq!(pairs, vec![
+///     ("/user/name".to_string(), br#""kero""#.to_vec()),
+///     ("/user/tags/0".to_string(), br#""a""#.to_vec()),
+/// ]);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn flatten<R, F>(reader: R, mut f: F) -> io::Result<JsonType>
+where
+    R: io::Read,
+    F: FnMut(String, &[u8], JsonType),
+{
+    let mut tracker = PathTracker::default();
+
+    scan_reader(reader, true, |token| {
+        tracker.token(&token);
+        if let Event::Scalar(json_type) = token.event {
+            f(tracker.pointer(), &token.bytes, json_type);
+        }
+    })
+}
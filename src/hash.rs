@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+use std::hash::Hasher;
+use std::io;
+
+use crate::events::{scan_reader, Event, Token};
+use crate::path::PathTracker;
+use crate::JsonType;
+
+/// A 64-bit FNV-1a hasher, its output is stable between
+/// executions, platforms and versions of Rust.
+pub(crate) struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Fnv64 {
+        Fnv64(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Validate the JSON from a stream of bytes and compute a signature of its structure.
+///
+/// Only the nesting of the containers, the number of keys and the types of the values
+/// are taken into account, two documents with the same shape but different values or
+/// keys have the same signature. This signature is stable and can be used as a cache key.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::structural_signature;
+///
+/// let a = structural_signature(&br#"{"name": "Kero", "age": 27}"#[..])?;
+/// let b = structural_signature(&br#"{"city": "Paris", "zip": 75}"#[..])?;
+/// assert_eq!(a, b);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn structural_signature<R: io::Read>(reader: R) -> io::Result<u64> {
+    let mut hasher = Fnv64::default();
+
+    scan_reader(reader, false, |token| {
+        let token = match token.event {
+            Event::StartObject => 0,
+            Event::EndObject => 1,
+            Event::StartArray => 2,
+            Event::EndArray => 3,
+            Event::Key => 4,
+            Event::Scalar(json_type) => 5 + json_type as u8,
+        };
+        hasher.write_u8(token);
+    })?;
+
+    Ok(hasher.finish())
+}
+
+/// A bloom filter of the keys of a JSON document, built by `validate_with_key_bloom`.
+///
+/// The keys are inserted as their raw bytes, without the quotes and without
+/// unescaping them, testing a key can return false positives but never false negatives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBloom {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+impl KeyBloom {
+    /// Construct an empty `KeyBloom` of at least `bits` bits that
+    /// computes `hashes` hashes for every key inserted or tested.
+    pub fn new(bits: usize, hashes: u32) -> KeyBloom {
+        let words = (bits.max(1) - 1) / 64 + 1;
+        KeyBloom { bits: vec![0; words], hashes: hashes.max(1) }
+    }
+
+    /// Insert a key in the filter.
+    pub fn insert(&mut self, key: &[u8]) {
+        for index in self.indexes(key) {
+            self.bits[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    /// Returns `true` if the key may have been inserted, `false` if it definitely was not.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.indexes(key).all(|index| self.bits[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    /// Returns the indexes of the bits associated with a key using double hashing.
+    fn indexes(&self, key: &[u8]) -> impl Iterator<Item = usize> {
+        let mut hasher = Fnv64::default();
+        hasher.write(key);
+        let h1 = hasher.finish();
+        hasher.write_u8(0xff);
+        let h2 = hasher.finish() | 1;
+
+        let len = self.bits.len() as u64 * 64;
+        (0..u64::from(self.hashes)).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+}
+
+impl Default for KeyBloom {
+    /// A filter of 64Kib with 4 hashes, enough for tens of thousands of keys.
+    fn default() -> KeyBloom {
+        KeyBloom::new(64 * 1024, 4)
+    }
+}
+
+/// Validate the JSON from a stream of bytes and insert all of the keys
+/// of its objects, at any depth, into the bloom filter.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::{validate_with_key_bloom, KeyBloom};
+///
+/// let text = r#"[{"id": 1, "name": "Kero"}, {"id": 2, "city": "Paris"}]"#;
+/// let (_, bloom) = validate_with_key_bloom(text.as_bytes(), KeyBloom::default())?;
+/// assert!(bloom.contains(b"city"));
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn validate_with_key_bloom<R: io::Read>(reader: R, mut bloom: KeyBloom) -> io::Result<(JsonType, KeyBloom)> {
+    let json_type = scan_reader(reader, true, |token| {
+        if token.event == Event::Key {
+            bloom.insert(&token.bytes[1..token.bytes.len() - 1]);
+        }
+    })?;
+
+    Ok((json_type, bloom))
+}
+
+/// Computes the hash of every container from the hashes of its children,
+/// two containers have the same hash if their minified bytes are the same.
+#[derive(Default)]
+pub(crate) struct SubtreeHasher {
+    hashers: Vec<Fnv64>,
+}
+
+impl SubtreeHasher {
+    /// Must be called for every captured token, in order, returns the
+    /// hash of the container that this token closes, if any.
+    pub fn token(&mut self, token: &Token) -> Option<u64> {
+        match token.event {
+            Event::StartObject | Event::StartArray => {
+                let mut hasher = Fnv64::default();
+                hasher.write_u8(if token.event == Event::StartObject { 0 } else { 1 });
+                self.hashers.push(hasher);
+                None
+            },
+            Event::EndObject | Event::EndArray => {
+                let hash = self.hashers.pop().expect("BUG: unbalanced containers").finish();
+                if let Some(parent) = self.hashers.last_mut() {
+                    parent.write_u8(2);
+                    parent.write_u64(hash);
+                }
+                Some(hash)
+            },
+            Event::Key | Event::Scalar(_) => {
+                if let Some(parent) = self.hashers.last_mut() {
+                    write_leaf(parent, &token.bytes);
+                }
+                None
+            },
+        }
+    }
+}
+
+/// Feed the raw bytes of a key or a scalar to the hasher.
+fn write_leaf(hasher: &mut Fnv64, bytes: &[u8]) {
+    hasher.write_u8(3);
+    hasher.write_usize(bytes.len());
+    hasher.write(bytes);
+}
+
+/// Validate the JSON from a stream of bytes and count the arrays and objects
+/// that are identical to one that appeared before, whitespaces excepted.
+///
+/// The nested containers are counted too, a big number indicates that deduplicating
+/// the subtrees of the document would help to compress it.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::repeated_subtrees;
+///
+/// let text = r#"[{"tags": ["a", "b"]}, {"tags": [ "a","b" ]}]"#;
+/// assert_eq!(repeated_subtrees(text.as_bytes())?, 2);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn repeated_subtrees<R: io::Read>(reader: R) -> io::Result<usize> {
+    let mut hasher = SubtreeHasher::default();
+    let mut seen = HashSet::new();
+    let mut repeated = 0;
+
+    scan_reader(reader, true, |token| {
+        if let Some(hash) = hasher.token(&token) {
+            if !seen.insert(hash) {
+                repeated += 1;
+            }
+        }
+    })?;
+
+    Ok(repeated)
+}
+
+/// The hashes of the containers of a JSON document, built by `merkle_tree`.
+///
+/// The hash of a container is computed from the hashes of its children,
+/// two containers have the same hash if their minified bytes are the same.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    /// The hash of the root value.
+    pub root: u64,
+    /// The JSON pointer and the hash of every array and object, in the order they close.
+    pub subtrees: Vec<(String, u64)>,
+}
+
+/// Validate the JSON from a stream of bytes and compute the hashes of all of its containers.
+///
+/// The branches of two documents that are not modified have the same hashes, this can be
+/// used to find the branches that differ or to cache some of them. The hashes are stable.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::merkle_tree;
+///
+/// let a = merkle_tree(&br#"{"a": [1, 2], "b": {"c": 3}}"#[..])?;
+/// let b = merkle_tree(&br#"{"a": [1, 2], "b": {"c": 4}}"#[..])?;
+///
+/// assert_ne!(a.root, b.root);
+/// assert_eq!(a.subtrees[0], b.subtrees[0]);
+/// assert_eq!(a.subtrees[0].0, "/a");
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn merkle_tree<R: io::Read>(reader: R) -> io::Result<MerkleTree> {
+    let mut hasher = SubtreeHasher::default();
+    let mut tracker = PathTracker::default();
+    let mut root = 0;
+    let mut subtrees = Vec::new();
+
+    scan_reader(reader, true, |token| {
+        let hash = hasher.token(&token);
+        tracker.token(&token);
+
+        match (hash, token.event) {
+            (Some(hash), _) => {
+                subtrees.push((tracker.pointer(), hash));
+                root = hash;
+            },
+            // A root scalar is hashed like the scalars in containers.
+            (None, Event::Scalar(_)) if token.depth == 0 => {
+                let mut hasher = Fnv64::default();
+                write_leaf(&mut hasher, &token.bytes);
+                root = hasher.finish();
+            },
+            _ => (),
+        }
+    })?;
+
+    Ok(MerkleTree { root, subtrees })
+}
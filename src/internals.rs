@@ -1,3 +1,8 @@
+//! The internal states and tables of the pushdown automaton.
+//!
+//! This module is only exposed with the `internals` feature, it can be used along
+//! with the `JsonChecker::step` method to visualize or debug the automaton.
+
 use self::Class::*;
 use self::State::*;
 
@@ -120,7 +125,7 @@ pub const STATE_TRANSITION_TABLE: [[State; 31]; 31] = [
 
                   white                                                   1-9                                                ABCDF     etc
              space  |  {    }  [   ]   :   ,   "   \    /  +   -   .   0   |   a   b   c   d   e   f   l   n   r   s   t   u   |   E    | */
-/*start  GO*/ [Go, Go,Woc, __,Wos, __, __, __, Wq, __, __, __, Mi, __, In, In, __, __, __, __, __, F1, __, N1, __, __, T1, __, __, __, __],
+/*start  GO*/ [Go, Go,Woc, __,Wos, __, __, __, Wq, __, __, __, Mi, __, Ze, In, __, __, __, __, __, F1, __, N1, __, __, T1, __, __, __, __],
 /*ok     OK*/ [Ok, Ok, __,Wcu, __, Ws, __, Wcm,__, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __],
 /*object OB*/ [Ob, Ob, __,Wec, __, __, __, __, St, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __],
 /*key    KE*/ [Ke, Ke, __, __, __, __, __, __, St, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __],
@@ -162,3 +167,29 @@ pub enum Mode {
     Object,
     String,
 }
+
+/// The action performed on the stack of modes by a transition.
+#[cfg(feature = "internals")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// The stack is left untouched.
+    None,
+    /// A mode has been pushed onto the stack.
+    Push(Mode),
+    /// A mode has been popped from the stack.
+    Pop(Mode),
+    /// The mode on top of the stack has been replaced, e.g. on a colon.
+    Flip(Mode, Mode),
+}
+
+/// Informations about a transition, returned by the `JsonChecker::step` method.
+#[cfg(feature = "internals")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StepInfo {
+    /// The new state of the automaton.
+    pub state: State,
+    /// The action performed on the stack.
+    pub action: Action,
+    /// The number of modes on the stack, the bottom `Mode::Done` excluded.
+    pub depth: usize,
+}
@@ -0,0 +1,168 @@
+use crate::internals::{Class, State, ASCII_CLASS, STATE_TRANSITION_TABLE};
+use crate::Error;
+
+/// Decodes the characters of the keys, escape sequences included,
+/// and checks them against a predicate.
+pub(crate) struct KeyChecker<F = fn(char) -> bool> {
+    predicate: F,
+    utf8: [u8; 4],
+    utf8_len: usize,
+    utf8_expected: usize,
+    unicode: u32,
+    high_surrogate: Option<u32>,
+}
+
+impl<F: FnMut(char) -> bool> KeyChecker<F> {
+    pub fn new(predicate: F) -> KeyChecker<F> {
+        KeyChecker {
+            predicate,
+            utf8: [0; 4],
+            utf8_len: 0,
+            utf8_expected: 0,
+            unicode: 0,
+            high_surrogate: None,
+        }
+    }
+
+    /// Forget about the bytes of the previous keys.
+    pub fn reset(&mut self) {
+        self.utf8_len = 0;
+        self.utf8_expected = 0;
+        self.unicode = 0;
+        self.high_surrogate = None;
+    }
+
+    /// Must be called for every byte of the keys along with the
+    /// state of the automaton before and after this byte.
+    pub fn byte(&mut self, state: State, next_state: State, byte: u8) -> Result<(), Error> {
+        // A multi-byte sequence can only be continued by non-ASCII bytes,
+        // the closing quote and the backslashes included.
+        if state == State::St && self.utf8_len != 0 && byte < 128 {
+            return Err(Error::InvalidKeyCharacter);
+        }
+
+        match (state, next_state) {
+            (State::St, State::Wq) => {
+                self.flush_surrogate()?;
+                self.reset();
+                Ok(())
+            },
+            (State::St, State::Es) => Ok(()),
+            (State::St, _) if byte < 128 => self.check(byte as char),
+            (State::St, _) => {
+                if self.utf8_len == 0 {
+                    self.utf8_expected = match byte {
+                        0xC0..=0xDF => 2,
+                        0xE0..=0xEF => 3,
+                        0xF0..=0xF7 => 4,
+                        _ => 1,
+                    };
+                }
+                self.utf8[self.utf8_len] = byte;
+                self.utf8_len += 1;
+                if self.utf8_len < self.utf8_expected {
+                    return Ok(());
+                }
+
+                let bytes = &self.utf8[..self.utf8_len];
+                let c = std::str::from_utf8(bytes).ok().and_then(|s| s.chars().next());
+                self.utf8_len = 0;
+                self.check(c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            },
+            (State::Es, State::U1) => {
+                self.unicode = 0;
+                Ok(())
+            },
+            (State::Es, _) => {
+                let c = match byte {
+                    b'b' => '\u{8}',
+                    b'f' => '\u{c}',
+                    b'n' => '\n',
+                    b'r' => '\r',
+                    b't' => '\t',
+                    _ => byte as char,
+                };
+                self.check(c)
+            },
+            (State::U1 | State::U2 | State::U3, _) => {
+                self.unicode = self.unicode * 16 + hex_value(byte);
+                Ok(())
+            },
+            (State::U4, _) => {
+                let code = self.unicode * 16 + hex_value(byte);
+                match (self.high_surrogate, code) {
+                    (Some(high), 0xDC00..=0xDFFF) => {
+                        self.high_surrogate = None;
+                        self.check_code(0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00))
+                    },
+                    (_, 0xD800..=0xDBFF) => {
+                        self.flush_surrogate()?;
+                        self.high_surrogate = Some(code);
+                        Ok(())
+                    },
+                    _ => self.check_code(code),
+                }
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// A high surrogate that is not followed by a low surrogate is an invalid character.
+    fn flush_surrogate(&mut self) -> Result<(), Error> {
+        match self.high_surrogate.take() {
+            Some(_) => self.test(char::REPLACEMENT_CHARACTER),
+            None => Ok(()),
+        }
+    }
+
+    fn check_code(&mut self, code: u32) -> Result<(), Error> {
+        self.check(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    fn check(&mut self, c: char) -> Result<(), Error> {
+        self.flush_surrogate()?;
+        self.test(c)
+    }
+
+    fn test(&mut self, c: char) -> Result<(), Error> {
+        if (self.predicate)(c) {
+            Ok(())
+        } else {
+            Err(Error::InvalidKeyCharacter)
+        }
+    }
+}
+
+/// Decodes a key, given with its quotes, that the automaton validated.
+///
+/// The escape sequences are decoded and the invalid characters
+/// are replaced by `U+FFFD`, like the `key_charset` option does.
+pub(crate) fn decode_key(bytes: &[u8]) -> String {
+    // The decoder rejects some of the invalid UTF-8 sequences, replace them first.
+    let bytes = String::from_utf8_lossy(bytes);
+    let mut key = String::new();
+    let mut decoder = KeyChecker::new(|c| {
+        key.push(c);
+        true
+    });
+
+    let mut state = State::St;
+    for &byte in &bytes.as_bytes()[1..] {
+        let class = if byte >= 128 { Class::CEtc } else { ASCII_CLASS[byte as usize] };
+        let next_state = STATE_TRANSITION_TABLE[state as usize][class as usize];
+        // Every character is accepted and the sequences are valid, it cannot fail.
+        let _ = decoder.byte(state, next_state, byte);
+        state = next_state;
+    }
+
+    key
+}
+
+/// Returns the value of an hexadecimal digit, the automaton ensures that it is valid.
+fn hex_value(byte: u8) -> u32 {
+    match byte {
+        b'0'..=b'9' => u32::from(byte - b'0'),
+        b'a'..=b'f' => u32::from(byte - b'a' + 10),
+        _ => u32::from(byte - b'A' + 10),
+    }
+}
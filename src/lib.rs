@@ -8,6 +8,10 @@
 //! Therefore this library accepts a single string or single integer as a valid JSON document,
 //! this way we follow the [`serde_json`](https://docs.rs/serde_json) rules.
 //!
+//! The automaton never recurses, the nested arrays and objects are tracked using an
+//! explicit stack, the nesting of a document is therefore only limited by the memory
+//! and by the `JsonChecker::with_max_depth` limit, not by the size of the thread stack.
+//!
 //! # Example: validate some bytes
 //!
 //! This example shows how you can give the library a simple slice
@@ -76,16 +80,41 @@
 //! ```
 //!
 
-use std::{fmt, io};
+use std::{fmt, fs, io};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use crate::internals::{State, Class, Mode};
 use crate::internals::{STATE_TRANSITION_TABLE, ASCII_CLASS};
+use crate::key::KeyChecker;
+#[cfg(feature = "internals")]
+use crate::internals::{Action, StepInfo};
 
 #[cfg(test)]
 mod tests;
+mod events;
+mod extract;
+mod hash;
+mod key;
+mod path;
+mod sort;
+mod stats;
+mod visit;
+#[cfg(feature = "internals")]
+pub mod internals;
+#[cfg(not(feature = "internals"))]
 mod internals;
 
+pub use crate::events::{JsonEvent, JsonEvents};
+pub use crate::extract::{array_preview, find_value, first_field_value, flatten, has_duplicate_elements};
+pub use crate::extract::{null_counts_by_key, string_spans, validate_span, value_end_offset};
+pub use crate::hash::{merkle_tree, repeated_subtrees, MerkleTree};
+pub use crate::hash::{structural_signature, validate_with_key_bloom, KeyBloom};
+pub use crate::sort::{is_canonical_minified, SortingChecker};
+pub use crate::stats::{validate_with_stats, EscapeCounts, Stats};
+pub use crate::visit::{validate_with_controllable_visitor, ValidationOutcome, Visitor};
+
 /// The error type returned by the `JsonChecker` type.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Error {
     InvalidCharacter,
     EmptyCurlyBraces,
@@ -97,9 +126,22 @@ pub enum Error {
     InvalidColon,
     InvalidState,
     IncompleteElement,
+    NonAsciiStructural,
+    DepthElementLimitExceeded { depth: usize },
+    TooManyEscapes,
+    UnexpectedRootType { found: JsonType, allowed: JsonTypeSet },
+    TotalStringBytesExceeded,
+    TrailingCharacters { offset: usize },
+    TypeLimitExceeded { ty: JsonType },
+    IntegerOutOfRange,
+    InvalidKeyCharacter,
+    StringTooLong,
+    NestingWeightExceeded,
 }
 
 impl From<Error> for io::Error {
+    // The io::Error::other function requires Rust 1.74.
+    #[allow(clippy::io_other_error)]
     fn from(err: Error) -> io::Error {
         io::Error::new(io::ErrorKind::Other, err)
     }
@@ -120,10 +162,78 @@ impl fmt::Display for Error {
             Error::InvalidColon => f.write_str("invalid colon"),
             Error::InvalidState => f.write_str("invalid state"),
             Error::IncompleteElement => f.write_str("incomplete element"),
+            Error::NonAsciiStructural => f.write_str("non-ASCII byte outside of a string"),
+            Error::DepthElementLimitExceeded { depth } => {
+                write!(f, "too many elements in a container at depth {}", depth)
+            },
+            Error::TooManyEscapes => f.write_str("too many escape sequences"),
+            Error::UnexpectedRootType { found, allowed } => {
+                write!(f, "unexpected root type {:?}, expected {}", found, allowed)
+            },
+            Error::TotalStringBytesExceeded => f.write_str("too many bytes in the strings"),
+            Error::TrailingCharacters { offset } => {
+                write!(f, "trailing characters at offset {}", offset)
+            },
+            Error::TypeLimitExceeded { ty } => write!(f, "too many values of type {:?}", ty),
+            Error::IntegerOutOfRange => f.write_str("integer out of range"),
+            Error::InvalidKeyCharacter => f.write_str("invalid character in a key"),
+            Error::StringTooLong => f.write_str("string too long"),
+            Error::NestingWeightExceeded => f.write_str("nesting weight exceeded"),
         }
     }
 }
 
+/// An `Error` along with the offset of the byte that triggered it
+/// and the nesting depth of the automaton at this byte.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DetailedError {
+    pub error: Error,
+    pub offset: usize,
+    pub depth_at_error: usize,
+}
+
+impl From<DetailedError> for io::Error {
+    // The io::Error::other function requires Rust 1.74.
+    #[allow(clippy::io_other_error)]
+    fn from(err: DetailedError) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, err)
+    }
+}
+
+impl std::error::Error for DetailedError {}
+
+impl fmt::Display for DetailedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {} (depth {})", self.error, self.offset, self.depth_at_error)
+    }
+}
+
+/// The position of a byte in a document, the lines and columns start at 1.
+///
+/// The columns are counted in bytes, not in characters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Compute the position of the byte at `offset` in the bytes.
+    fn at(bytes: &[u8], offset: usize) -> Position {
+        let before = &bytes[..offset];
+        let line = before.iter().filter(|b| **b == b'\n').count() + 1;
+        let line_start = before.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        Position { offset, line, column: offset - line_start + 1 }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
 /// Represents any valid JSON type.
 #[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub enum JsonType {
@@ -135,6 +245,131 @@ pub enum JsonType {
     Object,
 }
 
+impl JsonType {
+    const ALL: [JsonType; 6] = [
+        JsonType::Null,
+        JsonType::Bool,
+        JsonType::Number,
+        JsonType::String,
+        JsonType::Array,
+        JsonType::Object,
+    ];
+}
+
+/// A set of `JsonType`s.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct JsonTypeSet(u8);
+
+impl JsonTypeSet {
+    /// Construct an empty set.
+    pub fn new() -> JsonTypeSet {
+        JsonTypeSet(0)
+    }
+
+    /// Add a type to the set.
+    pub fn insert(&mut self, json_type: JsonType) {
+        self.0 |= 1 << json_type as u8;
+    }
+
+    /// Returns `true` if the set contains the type.
+    pub fn contains(&self, json_type: JsonType) -> bool {
+        self.0 & (1 << json_type as u8) != 0
+    }
+
+    /// Returns an iterator over the types of the set.
+    pub fn iter(&self) -> impl Iterator<Item = JsonType> + '_ {
+        JsonType::ALL.iter().copied().filter(move |t| self.contains(*t))
+    }
+}
+
+impl From<&[JsonType]> for JsonTypeSet {
+    fn from(types: &[JsonType]) -> JsonTypeSet {
+        let mut set = JsonTypeSet::new();
+        types.iter().for_each(|t| set.insert(*t));
+        set
+    }
+}
+
+impl fmt::Display for JsonTypeSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, json_type) in self.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" or ")?;
+            }
+            write!(f, "{:?}", json_type)?;
+        }
+        Ok(())
+    }
+}
+
+/// The maximum number of values of each `JsonType` in a document.
+///
+/// Used with the `JsonChecker::type_limits` method.
+///
+/// # Example
+///
+/// ```
+/// use oxidized_json_checker::{JsonChecker, JsonType, TypeLimits};
+///
+/// let limits = TypeLimits::new().max(JsonType::String, 100).max(JsonType::Number, 1000);
+/// let checker = JsonChecker::new(()).type_limits(limits);
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TypeLimits([Option<usize>; 6]);
+
+impl TypeLimits {
+    /// Construct a `TypeLimits` that limits nothing.
+    pub fn new() -> TypeLimits {
+        TypeLimits([None; 6])
+    }
+
+    /// Restrict the number of values of this type.
+    pub fn max(mut self, json_type: JsonType, max: usize) -> TypeLimits {
+        self.0[json_type as usize] = Some(max);
+        self
+    }
+
+    /// Returns the maximum number of values of this type, if any.
+    pub fn get(&self, json_type: JsonType) -> Option<usize> {
+        self.0[json_type as usize]
+    }
+}
+
+/// What happens when a string is longer than the `JsonChecker::max_string_len` limit.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum StringPolicy {
+    /// An `Error::StringTooLong` error is returned.
+    #[default]
+    Error,
+    /// The rest of the string is validated but it is counted
+    /// in the `JsonChecker::truncated_strings` counter.
+    Skip,
+}
+
+/// The status of the bytes processed so far by a `JsonChecker`.
+///
+/// Returned by the `JsonChecker::status` method.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PrefixStatus {
+    /// A byte made the document invalid, no more bytes can fix it.
+    Broken(Error),
+    /// A complete JSON value, optionally followed by whitespaces, has been read.
+    Complete(JsonType),
+    /// The bytes read are valid but do not form a complete JSON value yet.
+    NeedMore,
+}
+
+/// How complete the bytes are, returned by the `validate_completeness` function.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Completeness {
+    /// The bytes form a complete JSON value.
+    Complete,
+    /// The bytes are valid but do not form a complete JSON value yet.
+    Incomplete,
+    /// The bytes are invalid, no more bytes can fix them.
+    Invalid,
+}
+
 /// A convenient method to check and consume JSON from a stream of bytes.
 ///
 /// # Example
@@ -157,18 +392,345 @@ pub fn validate<R: io::Read>(reader: R) -> io::Result<JsonType> {
     Ok(outer_type)
 }
 
+/// A convenient method to check and consume JSON from a stream of bytes
+/// that must have one of the allowed types.
+///
+/// The type is checked as soon as the first byte of the value is read,
+/// the stream isn't consumed any further if it isn't allowed.
+///
+/// # Example
+///
+/// ```
+/// use oxidized_json_checker::{validate_root_in, JsonType};
+///
+/// let allowed = [JsonType::Object, JsonType::Array];
+/// assert!(validate_root_in(&b"[1, 2]"[..], &allowed).is_ok());
+/// assert!(validate_root_in(&b"\"string\""[..], &allowed).is_err());
+/// ```
+pub fn validate_root_in<R: io::Read>(reader: R, allowed: &[JsonType]) -> io::Result<JsonType> {
+    let mut checker = JsonChecker::new(reader).allowed_root_types(allowed);
+    io::copy(&mut checker, &mut io::sink())?;
+    let outer_type = checker.finish()?;
+    Ok(outer_type)
+}
+
+/// A convenient method to check and consume JSON from a file.
+pub fn validate_path<P: AsRef<Path>>(path: P) -> io::Result<JsonType> {
+    let file = fs::File::open(path)?;
+    validate(file)
+}
+
+/// A convenient method to check all of the `.json` files of a directory.
+///
+/// The other files are ignored, the sub-directories are only walked when `recursive`
+/// is `true`. An I/O error does not stop the walk, it is returned along with the path
+/// of the file or the directory that triggered it. The results are sorted by path.
+pub fn validate_dir<P: AsRef<Path>>(dir: P, recursive: bool) -> Vec<(PathBuf, io::Result<JsonType>)> {
+    let mut results = Vec::new();
+    let mut dirs = vec![dir.as_ref().to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                results.push((dir, Err(e)));
+                continue;
+            },
+        };
+
+        for entry in entries {
+            let (path, file_type) = match entry.and_then(|e| Ok((e.path(), e.file_type()?))) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    results.push((dir.clone(), Err(e)));
+                    continue;
+                },
+            };
+
+            if file_type.is_dir() {
+                if recursive {
+                    dirs.push(path);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                let result = validate_path(&path);
+                results.push((path, result));
+            }
+        }
+    }
+
+    results.sort_by(|(a, _), (b, _)| a.cmp(b));
+    results
+}
+
+/// A convenient method to check JSON from bytes that have already been read
+/// followed by the rest of a stream of bytes.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::{validate_with_prefix, JsonType};
+/// let (prefix, rest) = r#"{"a": 1}"#.as_bytes().split_at(3);
+///
+/// let json_type = validate_with_prefix(prefix, rest)?;
+/// assert_eq!(json_type, JsonType::Object);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn validate_with_prefix<R: io::Read>(prefix: &[u8], reader: R) -> io::Result<JsonType> {
+    let mut checker = JsonChecker::new(reader);
+    checker.feed(prefix)?;
+    io::copy(&mut checker, &mut io::sink())?;
+    let outer_type = checker.finish()?;
+    Ok(outer_type)
+}
+
+/// A convenient method to check the first JSON value of a stream of bytes.
+///
+/// Returns the type of the value, the bytes that have been read after the end
+/// of this value, the reader, this way the following values can be checked, and
+/// whether the reader has been fully consumed, only whitespaces followed the value.
+///
+/// To know if the reader is fully consumed it is read until a non-whitespace byte
+/// or the end of the stream is encountered, those bytes are part of the leftover.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::{validate_first_buffered, JsonType};
+///
+/// let text = r#"[1, 2] {"a": 1}"#;
+/// let (json_type, leftover, _reader, consumed) = validate_first_buffered(text.as_bytes())?;
+/// assert_eq!(json_type, JsonType::Array);
+/// assert_eq!(leftover, br#" {"a": 1}"#);
+/// assert!(!consumed);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn validate_first_buffered<R: io::Read>(mut reader: R) -> io::Result<(JsonType, Vec<u8>, R, bool)> {
+    fn is_whitespace(byte: &u8) -> bool {
+        matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+    }
+
+    let mut checker = JsonChecker::new(());
+    let mut buf = [0; 8192];
+
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        let mut value_end = None;
+        for (i, byte) in buf[..len].iter().enumerate() {
+            if checker.is_value_end(*byte) {
+                value_end = Some(i);
+                break;
+            }
+            checker.next_byte(*byte)?;
+        }
+
+        if let Some(i) = value_end {
+            let outer_type = checker.finish()?;
+            let mut leftover = buf[i..len].to_vec();
+
+            // We read until we find a non-whitespace byte to know if the reader is consumed.
+            while leftover.iter().all(is_whitespace) {
+                let len = match reader.read(&mut buf) {
+                    Ok(0) => return Ok((outer_type, leftover, reader, true)),
+                    Ok(len) => len,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                };
+                leftover.extend_from_slice(&buf[..len]);
+            }
+
+            return Ok((outer_type, leftover, reader, false));
+        }
+    }
+
+    let outer_type = checker.finish()?;
+    Ok((outer_type, Vec::new(), reader, true))
+}
+
 /// A convenient method to check and consume JSON from an `str`.
+///
+/// The automaton never decodes the UTF-8 sequences, the non-ASCII bytes are only
+/// accepted in the strings, the `str` type already guarantees that they are valid.
 pub fn validate_str(string: &str) -> Result<JsonType, Error> {
     validate_bytes(string.as_bytes())
 }
 
 /// A convenient method to check and consume JSON from a bytes slice.
+///
+/// The bytes that follow a complete value, whitespaces excepted,
+/// are reported with an `Error::TrailingCharacters` error.
 pub fn validate_bytes(bytes: &[u8]) -> Result<JsonType, Error> {
     let mut checker = JsonChecker::new(());
-    checker.next_bytes(bytes)?;
+    if let Err(error) = checker.next_bytes(bytes) {
+        // The state is not modified by a failing transition.
+        if checker.error_depth == 0 && is_value_complete(checker.state) {
+            return Err(Error::TrailingCharacters { offset: checker.offset() });
+        }
+        return Err(error);
+    }
     checker.finish()
 }
 
+/// A convenient method to check JSON embedded in a larger buffer.
+///
+/// The bytes in the range must be a complete JSON value, the offset of the errors
+/// are relative to the start of the whole buffer, not to the start of the range.
+///
+/// # Panics
+///
+/// Panics if the range is out of the bounds of the buffer.
+///
+/// # Example
+///
+/// ```
+/// use oxidized_json_checker::{validate_slice_range, Error, JsonType};
+///
+/// let bytes = b"\x00\x01\x02[1, 2]x";
+/// assert_eq!(validate_slice_range(bytes, 3..9), Ok(JsonType::Array));
+///
+/// let err = validate_slice_range(bytes, 3..10).unwrap_err();
+/// assert_eq!(err.error, Error::InvalidState);
+/// assert_eq!(err.offset, 9);
+/// ```
+pub fn validate_slice_range(bytes: &[u8], range: Range<usize>) -> Result<JsonType, DetailedError> {
+    let start = range.start;
+    let mut checker = JsonChecker::new(());
+
+    if let Err(error) = checker.next_bytes(&bytes[range]) {
+        let offset = start + checker.offset();
+        return Err(DetailedError { error, offset, depth_at_error: checker.error_depth });
+    }
+
+    let offset = start + checker.offset();
+    let depth_at_error = checker.stack.len() - 1;
+    checker.finish().map_err(|error| DetailedError { error, offset, depth_at_error })
+}
+
+/// A convenient method to check many independent JSON documents, the readers are
+/// validated lazily, one by one, while the iterator is consumed.
+///
+/// A single `JsonChecker` is used and reset between the readers.
+///
+/// # Example
+///
+/// ```
+/// use oxidized_json_checker::{validate_batch, JsonType};
+///
+/// let documents = vec![&b"[1, 2]"[..], b"{", b"null"];
+/// let results: Vec<_> = validate_batch(documents).map(|r| r.ok()).collect();
+/// assert_eq!(results, vec![Some(JsonType::Array), None, Some(JsonType::Null)]);
+/// ```
+pub fn validate_batch<I, R>(readers: I) -> impl Iterator<Item = io::Result<JsonType>>
+where
+    I: IntoIterator<Item = R>,
+    R: io::Read,
+{
+    let mut checker = JsonChecker::new(());
+    let mut buf = vec![0; 8192];
+
+    readers.into_iter().map(move |mut reader| {
+        checker.reset();
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            checker.next_bytes(&buf[..len])?;
+        }
+        Ok(checker.end()?)
+    })
+}
+
+/// A convenient method to check JSON from a bytes slice that returns, on error, the
+/// offset of the error along with the bytes around it, `context` bytes on each side.
+///
+/// The snippet can be shared in a bug report without exposing the whole document.
+///
+/// # Example
+///
+/// ```
+/// use oxidized_json_checker::{validate_with_snippet, Error};
+///
+/// let text = r#"{"name": "kero", "age": x}"#;
+/// let (error, offset, snippet) = validate_with_snippet(text.as_bytes(), 4).unwrap_err();
+/// assert_eq!((error, offset), (Error::InvalidState, 24));
+/// assert_eq!(snippet, br#"e": x}"#);
+/// ```
+pub fn validate_with_snippet(bytes: &[u8], context: usize) -> Result<JsonType, (Error, usize, Vec<u8>)> {
+    validate_slice_range(bytes, 0..bytes.len()).map_err(|DetailedError { error, offset, .. }| {
+        let start = offset.saturating_sub(context);
+        let end = offset.saturating_add(context).saturating_add(1).min(bytes.len());
+        (error, offset, bytes[start..end].to_vec())
+    })
+}
+
+/// A convenient method to check JSON from a bytes slice that can be empty.
+///
+/// Returns `Ok(None)` if the bytes are empty or only contain whitespaces,
+/// this way an empty config file can mean *use the defaults*.
+///
+/// # Example
+///
+/// ```
+/// use oxidized_json_checker::{validate_optional, JsonType};
+///
+/// assert_eq!(validate_optional(b" \n"), Ok(None));
+/// assert_eq!(validate_optional(b"{}"), Ok(Some(JsonType::Object)));
+/// assert!(validate_optional(b"{").is_err());
+/// ```
+pub fn validate_optional(bytes: &[u8]) -> Result<Option<JsonType>, Error> {
+    let mut checker = JsonChecker::new(());
+    checker.next_bytes(bytes)?;
+
+    if checker.state == State::Go {
+        return Ok(None);
+    }
+
+    checker.finish().map(Some)
+}
+
+/// A convenient method to know how complete the JSON in the bytes slice is,
+/// along with the type of the root value if at least one of its bytes is valid.
+///
+/// This is useful for live editors that must display a partial document.
+///
+/// # Example
+///
+/// ```
+/// use oxidized_json_checker::{validate_completeness, Completeness, JsonType};
+///
+/// let text = r#"{"a": [1, 2"#;
+/// let completeness = validate_completeness(text.as_bytes());
+/// assert_eq!(completeness, (Some(JsonType::Object), Completeness::Incomplete));
+/// ```
+pub fn validate_completeness(bytes: &[u8]) -> (Option<JsonType>, Completeness) {
+    let mut checker = JsonChecker::new(());
+    // The error is retrieved from the status.
+    let _ = checker.next_bytes(bytes);
+
+    let completeness = match checker.status() {
+        PrefixStatus::Broken(_) => Completeness::Invalid,
+        PrefixStatus::Complete(_) => Completeness::Complete,
+        PrefixStatus::NeedMore => Completeness::Incomplete,
+    };
+
+    (checker.outer_type, completeness)
+}
+
+/// A function called when a container opens or closes.
+type ContainerCallback = Box<dyn FnMut(JsonType, usize) + Send + Sync>;
+
 /// The `JsonChecker` is a `io::Read` adapter, it can be used like a pipe,
 /// reading bytes, checkings those and output the same bytes.
 ///
@@ -198,8 +760,33 @@ pub fn validate_bytes(bytes: &[u8]) -> Result<JsonType, Error> {
 pub struct JsonChecker<R> {
     state: State,
     error: Option<Error>,
+    error_depth: usize,
     outer_type: Option<JsonType>,
+    offset: usize,
     max_depth: usize,
+    max_container_depth: Option<usize>,
+    container_depth: usize,
+    depth_element_limits: Vec<usize>,
+    element_counts: Vec<usize>,
+    allow_leading_zeros: bool,
+    max_total_escapes: Option<usize>,
+    escapes: usize,
+    max_total_string_bytes: Option<usize>,
+    string_bytes: usize,
+    max_string_len: Option<usize>,
+    string_policy: StringPolicy,
+    string_len: usize,
+    truncated_strings: usize,
+    allowed_root_types: Option<JsonTypeSet>,
+    type_limits: Option<TypeLimits>,
+    type_counts: [usize; 6],
+    max_integer_magnitude: Option<u64>,
+    integer_magnitude: Option<u64>,
+    max_nesting_weight: Option<u64>,
+    nesting_weight: u64,
+    on_open: Option<ContainerCallback>,
+    on_close: Option<ContainerCallback>,
+    key_checker: Option<KeyChecker>,
     stack: Vec<Mode>,
     reader: R,
 }
@@ -214,7 +801,7 @@ impl<R> JsonChecker<R> {
     /// Construct a `JsonChecker. To continue the process, write to the `JsonChecker`
     /// like a sink, and then call `JsonChecker::finish` to obtain the final result.
     pub fn new(reader: R) -> JsonChecker<R> {
-        JsonChecker::with_max_depth(reader, usize::max_value())
+        JsonChecker::with_max_depth(reader, usize::MAX)
     }
 
     /// Construct a `JsonChecker` and restrict the level of maximum nesting.
@@ -224,13 +811,226 @@ impl<R> JsonChecker<R> {
         JsonChecker {
             state: State::Go,
             error: None,
+            error_depth: 0,
             outer_type: None,
+            offset: 0,
             max_depth,
+            max_container_depth: None,
+            container_depth: 0,
+            depth_element_limits: Vec::new(),
+            element_counts: Vec::new(),
+            allow_leading_zeros: false,
+            max_total_escapes: None,
+            escapes: 0,
+            max_total_string_bytes: None,
+            string_bytes: 0,
+            max_string_len: None,
+            string_policy: StringPolicy::Error,
+            string_len: 0,
+            truncated_strings: 0,
+            allowed_root_types: None,
+            type_limits: None,
+            type_counts: [0; 6],
+            max_integer_magnitude: None,
+            integer_magnitude: None,
+            max_nesting_weight: None,
+            nesting_weight: 0,
+            on_open: None,
+            on_close: None,
+            key_checker: None,
             stack: vec![Mode::Done],
             reader,
         }
     }
 
+    /// Restrict the maximum number of nested arrays and objects.
+    ///
+    /// Unlike the `JsonChecker::with_max_depth` limit, only the containers are counted,
+    /// `[[1]]` has a container depth of 2 whatever the internal state of the automaton is.
+    pub fn max_container_depth(mut self, max_container_depth: usize) -> JsonChecker<R> {
+        self.max_container_depth = Some(max_container_depth);
+        self
+    }
+
+    /// Restrict the number of elements of the arrays and objects depending on their depth.
+    ///
+    /// The limit at index `d` applies to the containers at depth `d`, the root container
+    /// being at depth 0, the last limit applies to the containers that are deeper.
+    pub fn depth_element_limits(mut self, limits: Vec<usize>) -> JsonChecker<R> {
+        self.depth_element_limits = limits;
+        self
+    }
+
+    /// Accept the numbers with leading zeros, like `013`, that are forbidden by JSON.
+    pub fn allow_leading_zeros(mut self, allow: bool) -> JsonChecker<R> {
+        self.allow_leading_zeros = allow;
+        self
+    }
+
+    /// Restrict the number of escape sequences in the strings of the whole document.
+    ///
+    /// This protects the consumers that unescape all of the strings of a document.
+    pub fn max_total_escapes(mut self, max_total_escapes: usize) -> JsonChecker<R> {
+        self.max_total_escapes = Some(max_total_escapes);
+        self
+    }
+
+    /// Restrict the number of bytes of the content of all of the strings,
+    /// keys included, the escape sequences are counted as they are written.
+    ///
+    /// Unlike a limit on the length of the strings, this protects against
+    /// documents with many strings that are individually small.
+    pub fn max_total_string_bytes(mut self, max_total_string_bytes: usize) -> JsonChecker<R> {
+        self.max_total_string_bytes = Some(max_total_string_bytes);
+        self
+    }
+
+    /// Restrict the number of bytes of the content of every string, keys included,
+    /// the escape sequences are counted as they are written.
+    ///
+    /// What happens to the strings that are too long depends on the `JsonChecker::string_policy`.
+    pub fn max_string_len(mut self, max_string_len: usize) -> JsonChecker<R> {
+        self.max_string_len = Some(max_string_len);
+        self
+    }
+
+    /// Define what happens when a string is longer than the `JsonChecker::max_string_len` limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+    /// use oxidized_json_checker::{JsonChecker, StringPolicy};
+    ///
+    /// let mut checker = JsonChecker::new(()).max_string_len(4).string_policy(StringPolicy::Skip);
+    /// checker.feed(br#"["kero", "clementine", "\"long\""]"#)?;
+    /// assert_eq!(checker.truncated_strings(), 2);
+    /// checker.finish()?;
+    /// # Ok(()) }
+    /// # fmain().unwrap()
+    /// ```
+    pub fn string_policy(mut self, policy: StringPolicy) -> JsonChecker<R> {
+        self.string_policy = policy;
+        self
+    }
+
+    /// Restrict the types allowed for the root value, the type
+    /// is checked as soon as the first byte of the value is processed.
+    pub fn allowed_root_types(mut self, allowed: &[JsonType]) -> JsonChecker<R> {
+        self.allowed_root_types = Some(JsonTypeSet::from(allowed));
+        self
+    }
+
+    /// Restrict the number of values of each type in the whole document,
+    /// the elements of the containers and the containers themselves are counted.
+    pub fn type_limits(mut self, limits: TypeLimits) -> JsonChecker<R> {
+        self.type_limits = Some(limits);
+        self
+    }
+
+    /// Restrict the absolute value of the integers, the numbers with
+    /// a fraction or an exponent are not restricted.
+    ///
+    /// This is useful when the integers are stored in fixed-width columns.
+    pub fn max_integer_magnitude(mut self, max: u64) -> JsonChecker<R> {
+        self.max_integer_magnitude = Some(max);
+        self
+    }
+
+    /// Restrict the sum of the depths of all of the values of the document, the depth
+    /// of a value being the number of containers enclosing it.
+    ///
+    /// Unlike a depth limit, this catches the documents that are both deep and wide,
+    /// like a huge array of deeply nested objects.
+    pub fn max_nesting_weight(mut self, max: u64) -> JsonChecker<R> {
+        self.max_nesting_weight = Some(max);
+        self
+    }
+
+    /// Call a function with the type and the depth of every array and object
+    /// that opens, the root container being at depth 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::io;
+    /// use oxidized_json_checker::JsonChecker;
+    ///
+    /// let text = r#"{"a": [1, {}]}"#;
+    /// let mut checker = JsonChecker::new(text.as_bytes())
+    ///     .on_open(|ty, depth| println!("{:indent$}{:?} {{", "", ty, indent = depth * 2))
+    ///     .on_close(|ty, depth| println!("{:indent$}}} {:?}", "", ty, indent = depth * 2));
+    ///
+    /// io::copy(&mut checker, &mut io::sink())?;
+    /// checker.finish()?;
+    /// # Ok(()) }
+    /// # fmain().unwrap()
+    /// ```
+    pub fn on_open<F>(mut self, f: F) -> JsonChecker<R>
+    where F: FnMut(JsonType, usize) + Send + Sync + 'static,
+    {
+        self.on_open = Some(Box::new(f));
+        self
+    }
+
+    /// Call a function with the type and the depth of every array and object
+    /// that closes, the depth is the same as when the container opened.
+    pub fn on_close<F>(mut self, f: F) -> JsonChecker<R>
+    where F: FnMut(JsonType, usize) + Send + Sync + 'static,
+    {
+        self.on_close = Some(Box::new(f));
+        self
+    }
+
+    /// Restrict the characters allowed in the keys of the objects, the escape
+    /// sequences are decoded and the invalid characters are replaced by `U+FFFD`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use oxidized_json_checker::{JsonChecker, Error};
+    ///
+    /// let mut checker = JsonChecker::new(())
+    ///     .key_charset(|c| c.is_ascii_alphanumeric() || c == '_');
+    ///
+    /// assert_eq!(checker.feed(br#"{"user_id": 1, "user\u0020name""#), Err(Error::InvalidKeyCharacter));
+    /// ```
+    pub fn key_charset(mut self, predicate: fn(char) -> bool) -> JsonChecker<R> {
+        self.key_checker = Some(KeyChecker::new(predicate));
+        self
+    }
+
+    /// Process bytes that do not come from the internal reader.
+    ///
+    /// This is useful when some bytes were already read, the internal reader
+    /// can then be used to read the remaining ones.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.next_bytes(bytes)
+    }
+
+    /// Process a single byte and returns informations about the transition
+    /// of the automaton, useful to visualize or debug its inner workings.
+    ///
+    /// Errors *fuse* the checker like when bytes are processed by any other method.
+    #[cfg(feature = "internals")]
+    pub fn step(&mut self, byte: u8) -> Result<StepInfo, Error> {
+        let len = self.stack.len();
+        let last = self.stack.last().copied();
+
+        self.next_byte(byte)?;
+
+        let top = self.stack.last().copied();
+        let action = match (last, top) {
+            (_, Some(top)) if self.stack.len() > len => Action::Push(top),
+            (Some(last), _) if self.stack.len() < len => Action::Pop(last),
+            (Some(last), Some(top)) if last != top => Action::Flip(last, top),
+            _ => Action::None,
+        };
+
+        Ok(StepInfo { state: self.state, action, depth: self.stack.len() - 1 })
+    }
+
     #[inline]
     #[cfg(feature = "nightly")]
     fn next_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
@@ -240,7 +1040,7 @@ impl<R> JsonChecker<R> {
         // By using u8x8 instead of u8x16 we lost 2s on 16s but
         // we are less prone to find state change requirements.
         for chunk in bytes.chunks(u8x8::lanes()) {
-            if chunk.len() == u8x8::lanes() && self.state == State::St {
+            if chunk.len() == u8x8::lanes() && self.state == State::St && self.can_skip_string_bytes() {
                 // Load the bytes into a SIMD type
                 let bytes = u8x8::from_slice_unaligned(chunk);
 
@@ -265,6 +1065,8 @@ impl<R> JsonChecker<R> {
                    bytes.eq(cwhites3).any()
                 {
                     chunk.iter().try_for_each(|b| self.next_byte(*b))?;
+                } else {
+                    self.offset += chunk.len();
                 }
 
                 // Now that we checked that these bytes will not change
@@ -278,6 +1080,15 @@ impl<R> JsonChecker<R> {
         Ok(())
     }
 
+    /// Returns `true` if the plain bytes of the strings do not need to be processed
+    /// one by one, i.e. no option counts or checks them.
+    #[cfg(feature = "nightly")]
+    fn can_skip_string_bytes(&self) -> bool {
+        self.max_total_string_bytes.is_none()
+            && self.max_string_len.is_none()
+            && self.key_checker.is_none()
+    }
+
     #[inline]
     #[cfg(not(feature = "nightly"))]
     fn next_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
@@ -305,48 +1116,93 @@ impl<R> JsonChecker<R> {
 
             // Get the next state from the state transition table and
             // perform one of the actions.
-            let next_state = STATE_TRANSITION_TABLE[jc.state as usize][next_class as usize];
+            let mut next_state = STATE_TRANSITION_TABLE[jc.state as usize][next_class as usize];
 
-            // Save the type we met if not already saved.
-            if jc.outer_type.is_none() {
+            // Digits after a leading zero continue the integer when explicitly allowed.
+            if next_state == State::Invalid && jc.state == State::Ze && jc.allow_leading_zeros {
+                if let Class::CZero | Class::CDigit = next_class {
+                    next_state = State::In;
+                }
+            }
+
+            // Count the escape sequences, a unicode escape is a single escape sequence.
+            if next_state == State::Es {
+                if let Some(max) = jc.max_total_escapes {
+                    if jc.escapes >= max {
+                        return Err(Error::TooManyEscapes);
+                    }
+                    jc.escapes += 1;
+                }
+            }
+
+            // Count the bytes between the quotes of the strings.
+            if jc.max_total_string_bytes.is_some() || jc.max_string_len.is_some() {
+                if is_in_string(jc.state) && next_state == State::Wq {
+                    jc.string_len = 0;
+                } else if is_in_string(jc.state) {
+                    jc.count_string_byte()?;
+                }
+            }
+
+            // Compute the magnitude of the integers, `None` means that it overflowed.
+            if jc.max_integer_magnitude.is_some() {
                 match next_state {
-                    State::N1 => jc.outer_type = Some(JsonType::Null),
-                    State::T1 | State::F1 => jc.outer_type = Some(JsonType::Bool),
-                    State::In => jc.outer_type = Some(JsonType::Number),
-                    State::Wq => jc.outer_type = Some(JsonType::String),
-                    State::Wos => jc.outer_type = Some(JsonType::Array),
-                    State::Woc => jc.outer_type = Some(JsonType::Object),
+                    State::Ze | State::In => {
+                        if !matches!(jc.state, State::Ze | State::In) {
+                            jc.integer_magnitude = Some(0);
+                        }
+                        let digit = u64::from(next_byte - b'0');
+                        jc.integer_magnitude = jc.integer_magnitude
+                            .and_then(|m| m.checked_mul(10))
+                            .and_then(|m| m.checked_add(digit));
+                    },
+                    State::Ok | State::Wcu | State::Ws | State::Wcm => jc.check_integer_magnitude()?,
                     _ => (),
                 }
             }
 
+            // Check the characters of the keys, the mode is `Key` until the colon.
+            if let Some(key_checker) = &mut jc.key_checker {
+                if next_state != State::Invalid && jc.stack.last() == Some(&Mode::Key) {
+                    key_checker.byte(jc.state, next_state, next_byte)?;
+                }
+            }
+
+            // Save the type of the values we meet and enforce the limits on them.
+            if let Some(value_type) = value_type(jc.state, next_state) {
+                jc.start_value(value_type)?;
+            }
+
             match next_state {
                 State::Wec => { // Empty }
                     if !jc.pop(Mode::Key) {
                         return Err(Error::EmptyCurlyBraces);
                     }
+                    jc.pop_container(JsonType::Object);
                     jc.state = State::Ok;
                 },
                 State::Wcu => { // }
                     if !jc.pop(Mode::Object) {
                         return Err(Error::OrphanCurlyBrace);
                     }
+                    jc.pop_container(JsonType::Object);
                     jc.state = State::Ok;
                 },
                 State::Ws => { // ]
                     if !jc.pop(Mode::Array) {
                         return Err(Error::OrphanSquareBrace);
                     }
+                    jc.pop_container(JsonType::Array);
                     jc.state = State::Ok;
                 },
                 State::Woc => { // {
-                    if !jc.push(Mode::Key) {
+                    if !jc.push(Mode::Key) || !jc.push_container(JsonType::Object) {
                         return Err(Error::MaxDepthReached);
                     }
                     jc.state = State::Ob;
                 },
                 State::Wos => { // [
-                    if !jc.push(Mode::Array) {
+                    if !jc.push(Mode::Array) || !jc.push_container(JsonType::Array) {
                         return Err(Error::MaxDepthReached);
                     }
                     jc.state = State::Ar;
@@ -390,6 +1246,11 @@ impl<R> JsonChecker<R> {
                     jc.state = State::Va;
                 },
                 State::Invalid => {
+                    // A non-ASCII byte is only valid inside a string, out of them
+                    // it is most likely an encoding issue or a corrupted document.
+                    if next_byte >= 128 && !is_in_string(jc.state) {
+                        return Err(Error::NonAsciiStructural);
+                    }
                     return Err(Error::InvalidState)
                 },
 
@@ -400,16 +1261,99 @@ impl<R> JsonChecker<R> {
             Ok(())
         }
 
+        // The failing transition can modify the stack, we save the depth before.
+        let depth = self.stack.len() - 1;
+
         // By catching returned errors when this `JsonChecker` is used we *fuse*
         // the checker and ensure the user don't use a checker in an invalid state.
         if let Err(error) = internal_next_byte(self, next_byte) {
             self.error = Some(error);
+            self.error_depth = depth;
             return Err(error);
         }
 
+        self.offset += 1;
+
         Ok(())
     }
 
+    /// Returns the number of bytes that have been successfully processed.
+    ///
+    /// When an error has been encountered this is the offset of the invalid byte.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of strings that were longer than the `JsonChecker::max_string_len`
+    /// limit and skipped, see `StringPolicy::Skip`.
+    pub fn truncated_strings(&self) -> usize {
+        self.truncated_strings
+    }
+
+    /// Returns the status of the bytes that have been processed until now.
+    ///
+    /// This method can be called between two reads to detect as soon as possible
+    /// that the document can't be valid, e.g. to close a connection early.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::io::Read;
+    /// use oxidized_json_checker::{JsonChecker, JsonType, PrefixStatus};
+    ///
+    /// let mut checker = JsonChecker::new(&b"[1, 2]"[..]);
+    /// let mut buf = [0; 3];
+    ///
+    /// checker.read(&mut buf)?;
+    /// assert_eq!(checker.status(), PrefixStatus::NeedMore);
+    ///
+    /// checker.read_to_end(&mut Vec::new())?;
+    /// assert_eq!(checker.status(), PrefixStatus::Complete(JsonType::Array));
+    /// # Ok(()) }
+    /// # fmain().unwrap()
+    /// ```
+    pub fn status(&self) -> PrefixStatus {
+        if let Some(error) = self.error {
+            return PrefixStatus::Broken(error);
+        }
+
+        // The integer that ends the document is only checked by `finish`.
+        if let Err(error) = self.check_integer_magnitude() {
+            return PrefixStatus::Broken(error);
+        }
+
+        match self.outer_type {
+            Some(outer_type) if self.is_complete() => PrefixStatus::Complete(outer_type),
+            _ => PrefixStatus::NeedMore,
+        }
+    }
+
+    /// Returns `true` if the bytes processed until now form a complete JSON value.
+    fn is_complete(&self) -> bool {
+        is_value_complete(self.state) && self.stack.last() == Some(&Mode::Done)
+    }
+
+    /// Returns `true` if a complete JSON value has been processed
+    /// and the next byte cannot be part of this value.
+    fn is_value_end(&self, next_byte: u8) -> bool {
+        if !self.is_complete() {
+            return false;
+        }
+
+        // A number stops on the first byte that does not continue it.
+        let next_class = if next_byte >= 128 {
+            Class::CEtc
+        } else {
+            ASCII_CLASS[next_byte as usize]
+        };
+
+        next_class == Class::Invalid || !matches!(
+            STATE_TRANSITION_TABLE[self.state as usize][next_class as usize],
+            State::In | State::Fr | State::Fs | State::E1 | State::E2 | State::E3
+        )
+    }
+
     /// The `JsonChecker::finish` method must be called after all of the characters
     /// have been processed.
     ///
@@ -421,20 +1365,47 @@ impl<R> JsonChecker<R> {
 
     /// The `JsonChecker::into_inner` does the same as the `JsonChecker::finish`
     /// method but returns the internal reader along with the JSON type guessed.
-    pub fn into_inner(mut self) -> Result<(R, JsonType), Error> {
-        let is_state_valid = match self.state {
-            State::Ok | State::In | State::Fr | State::Fs | State::E3 => true,
-            _ => false,
-        };
+    pub fn into_inner(self) -> Result<(R, JsonType), Error> {
+        // We do not need to catch this error to *fuse* the checker because this method
+        // consumes the checker, it cannot be reused after an error has been thrown.
+        let outer_type = self.end()?;
+        Ok((self.reader, outer_type))
+    }
 
-        if is_state_valid && self.pop(Mode::Done) {
-            let outer_type = self.outer_type.expect("BUG: the outer type must have been guessed");
-            return Ok((self.reader, outer_type))
+    /// Reset the checker to validate a new document, the configuration
+    /// is kept and the memory already allocated is reused.
+    pub fn reset(&mut self) {
+        self.state = State::Go;
+        self.error = None;
+        self.error_depth = 0;
+        self.outer_type = None;
+        self.offset = 0;
+        self.container_depth = 0;
+        self.element_counts.clear();
+        self.escapes = 0;
+        self.string_bytes = 0;
+        self.string_len = 0;
+        self.truncated_strings = 0;
+        self.type_counts = [0; 6];
+        self.integer_magnitude = None;
+        self.nesting_weight = 0;
+        if let Some(key_checker) = &mut self.key_checker {
+            key_checker.reset();
         }
+        self.stack.clear();
+        self.stack.push(Mode::Done);
+    }
 
-        // We do not need to catch this error to *fuse* the checker because this method
-        // consumes the checker, it cannot be reused after an error has been thrown.
-        Err(Error::IncompleteElement)
+    /// Returns the JSON type guessed if the bytes processed form a complete JSON value.
+    fn end(&self) -> Result<JsonType, Error> {
+        self.check_integer_magnitude()?;
+
+        // A fraction point without digits is accepted at the end of the document.
+        let is_done = is_accepting(self.state) && self.stack.last() == Some(&Mode::Done);
+        match self.outer_type {
+            Some(outer_type) if is_done => Ok(outer_type),
+            _ => Err(Error::IncompleteElement),
+        }
     }
 
     /// Push a mode onto the stack. Returns false if max depth is reached.
@@ -443,7 +1414,107 @@ impl<R> JsonChecker<R> {
             return false;
         }
         self.stack.push(mode);
-        return true;
+        true
+    }
+
+    /// Count a byte of the content of a string and enforce the limits on it.
+    fn count_string_byte(&mut self) -> Result<(), Error> {
+        if let Some(max) = self.max_total_string_bytes {
+            if self.string_bytes >= max {
+                return Err(Error::TotalStringBytesExceeded);
+            }
+            self.string_bytes += 1;
+        }
+
+        if let Some(max) = self.max_string_len {
+            // The length only reaches the limit once per string.
+            if self.string_len == max {
+                match self.string_policy {
+                    StringPolicy::Error => return Err(Error::StringTooLong),
+                    StringPolicy::Skip => self.truncated_strings += 1,
+                }
+            }
+            self.string_len = self.string_len.saturating_add(1);
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if the integer that ends in the current state is too big.
+    fn check_integer_magnitude(&self) -> Result<(), Error> {
+        if let Some(max) = self.max_integer_magnitude {
+            let is_integer = matches!(self.state, State::Ze | State::In);
+            let in_range = matches!(self.integer_magnitude, Some(m) if m <= max);
+            if is_integer && !in_range {
+                return Err(Error::IntegerOutOfRange);
+            }
+        }
+        Ok(())
+    }
+
+    /// Save the type of the first value and count the values in their containers.
+    fn start_value(&mut self, value_type: JsonType) -> Result<(), Error> {
+        if self.outer_type.is_none() {
+            if let Some(allowed) = self.allowed_root_types {
+                if !allowed.contains(value_type) {
+                    return Err(Error::UnexpectedRootType { found: value_type, allowed });
+                }
+            }
+            self.outer_type = Some(value_type);
+        }
+
+        if let Some(limits) = self.type_limits {
+            let count = &mut self.type_counts[value_type as usize];
+            *count += 1;
+            if limits.get(value_type).is_some_and(|max| *count > max) {
+                return Err(Error::TypeLimitExceeded { ty: value_type });
+            }
+        }
+
+        if let Some(max) = self.max_nesting_weight {
+            self.nesting_weight = self.nesting_weight.saturating_add(self.container_depth as u64);
+            if self.nesting_weight > max {
+                return Err(Error::NestingWeightExceeded);
+            }
+        }
+
+        let depth = self.element_counts.len().saturating_sub(1);
+        if let Some(count) = self.element_counts.last_mut() {
+            *count += 1;
+            let limits = &self.depth_element_limits;
+            let limit = limits.get(depth).or_else(|| limits.last());
+            if limit.is_some_and(|limit| *count > *limit) {
+                return Err(Error::DepthElementLimitExceeded { depth });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count a new nested container. Returns false if max container depth is reached.
+    fn push_container(&mut self, container_type: JsonType) -> bool {
+        if let Some(max) = self.max_container_depth {
+            if self.container_depth >= max {
+                return false;
+            }
+        }
+        if !self.depth_element_limits.is_empty() {
+            self.element_counts.push(0);
+        }
+        if let Some(on_open) = &mut self.on_open {
+            on_open(container_type, self.container_depth);
+        }
+        self.container_depth += 1;
+        true
+    }
+
+    /// Forget about the innermost container.
+    fn pop_container(&mut self, container_type: JsonType) {
+        self.element_counts.pop();
+        self.container_depth -= 1;
+        if let Some(on_close) = &mut self.on_close {
+            on_close(container_type, self.container_depth);
+        }
     }
 
     /// Pop the stack, assuring that the current mode matches the expectation.
@@ -453,6 +1524,38 @@ impl<R> JsonChecker<R> {
     }
 }
 
+/// Returns `true` if the automaton is reading the content of a string in this state.
+fn is_in_string(state: State) -> bool {
+    matches!(state, State::St | State::Es | State::U1 | State::U2 | State::U3 | State::U4)
+}
+
+/// Returns `true` if a value can end in this state.
+fn is_accepting(state: State) -> bool {
+    matches!(state, State::Ok | State::Ze | State::In | State::Fr | State::Fs | State::E3)
+}
+
+/// Returns `true` if a value is complete in this state, unlike `is_accepting`
+/// a fraction point must be followed by at least one digit.
+fn is_value_complete(state: State) -> bool {
+    is_accepting(state) && state != State::Fr
+}
+
+/// Returns the type of the value that starts with this transition, if any.
+fn value_type(state: State, next_state: State) -> Option<JsonType> {
+    match state {
+        State::Go | State::Va | State::Ar => match next_state {
+            State::N1 => Some(JsonType::Null),
+            State::T1 | State::F1 => Some(JsonType::Bool),
+            State::Mi | State::Ze | State::In => Some(JsonType::Number),
+            State::Wq | State::St => Some(JsonType::String),
+            State::Wos => Some(JsonType::Array),
+            State::Woc => Some(JsonType::Object),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 impl<R: io::Read> io::Read for JsonChecker<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         // If an error have already been encountered we return it,
@@ -0,0 +1,61 @@
+use crate::events::{Event, Token};
+use crate::key::decode_key;
+
+/// The current element of a container.
+enum Frame {
+    Array(Option<usize>),
+    Object(Option<String>),
+}
+
+/// Follows the tokens to know the JSON pointer of the current value.
+///
+/// The tokens must have been captured for the keys to be known.
+#[derive(Default)]
+pub(crate) struct PathTracker {
+    frames: Vec<Frame>,
+}
+
+impl PathTracker {
+    /// Must be called for every token, in order.
+    pub fn token(&mut self, token: &Token) {
+        match token.event {
+            Event::Key => if let Some(Frame::Object(key)) = self.frames.last_mut() {
+                // The bytes of the key are captured along with its quotes.
+                *key = Some(decode_key(&token.bytes));
+            },
+            Event::EndObject | Event::EndArray => {
+                self.frames.pop();
+            },
+            Event::Scalar(_) | Event::StartObject | Event::StartArray => {
+                if let Some(Frame::Array(index)) = self.frames.last_mut() {
+                    *index = Some(index.map_or(0, |i| i + 1));
+                }
+                match token.event {
+                    Event::StartObject => self.frames.push(Frame::Object(None)),
+                    Event::StartArray => self.frames.push(Frame::Array(None)),
+                    _ => (),
+                }
+            },
+        }
+    }
+
+    /// The JSON pointer of the last value seen, the keys are decoded
+    /// then escaped as described by the RFC 6901.
+    pub fn pointer(&self) -> String {
+        let mut pointer = String::new();
+        for frame in &self.frames {
+            match frame {
+                Frame::Array(Some(index)) => {
+                    pointer.push('/');
+                    pointer.push_str(&index.to_string());
+                },
+                Frame::Object(Some(key)) => {
+                    pointer.push('/');
+                    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                },
+                _ => (),
+            }
+        }
+        pointer
+    }
+}
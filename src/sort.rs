@@ -0,0 +1,227 @@
+use std::io;
+use std::ops::Range;
+
+use crate::events::{Event, Scanner, Token};
+use crate::internals::State;
+use crate::{Error, JsonType};
+
+/// A container that is being re-emitted.
+enum Frame {
+    Array { first: bool },
+    /// The members of an object, the keys are given with their quotes.
+    Object { members: Vec<(Vec<u8>, Vec<u8>)> },
+}
+
+/// A `io::Read` adapter that validates the JSON from a reader and
+/// outputs it minified with the keys of all of its objects sorted.
+///
+/// The keys are sorted by their raw bytes, without unescaping them, the members with
+/// the same key keep their order. The values are output as they are written.
+///
+/// The objects are only output once closed, the members of an object are therefore
+/// kept in memory, the root object being entirely kept in memory.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::Read;
+/// use oxidized_json_checker::{SortingChecker, JsonType};
+///
+/// let text = r#"{"name": "kero", "age": 27, "tags": [{"z": 1, "y": 2}]}"#;
+/// let mut checker = SortingChecker::new(text.as_bytes());
+///
+/// let mut output = String::new();
+/// checker.read_to_string(&mut output)?;
+/// assert_eq!(output, r#"{"age":27,"name":"kero","tags":[{"y":2,"z":1}]}"#);
+/// assert_eq!(checker.finish()?, JsonType::Object);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub struct SortingChecker<R> {
+    reader: R,
+    scanner: Scanner,
+    sorter: Sorter,
+    buffer: Box<[u8]>,
+    pos: usize,
+    eof: bool,
+}
+
+impl<R> SortingChecker<R> {
+    /// Construct a `SortingChecker` that reads from the given reader.
+    pub fn new(reader: R) -> SortingChecker<R> {
+        SortingChecker {
+            reader,
+            scanner: Scanner::new(true),
+            sorter: Sorter::default(),
+            buffer: vec![0; 8192].into_boxed_slice(),
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    /// Consumes the `SortingChecker` and returns the JSON type guessed,
+    /// the bytes must have been read until the end of the reader.
+    pub fn finish(self) -> Result<JsonType, Error> {
+        self.scanner.finish()
+    }
+}
+
+/// Re-emits the tokens with the keys sorted.
+#[derive(Default)]
+struct Sorter {
+    frames: Vec<Frame>,
+    output: Vec<u8>,
+}
+
+impl Sorter {
+    fn token(&mut self, token: Token) {
+        if let Event::Scalar(_) | Event::StartObject | Event::StartArray = token.event {
+            if let Some(Frame::Array { first }) = self.frames.last_mut() {
+                if !std::mem::replace(first, false) {
+                    self.emit(b",");
+                }
+            }
+        }
+
+        match token.event {
+            Event::Scalar(_) => self.emit(&token.bytes),
+            Event::StartArray => {
+                self.emit(b"[");
+                self.frames.push(Frame::Array { first: true });
+            },
+            Event::EndArray => {
+                self.frames.pop();
+                self.emit(b"]");
+            },
+            Event::StartObject => self.frames.push(Frame::Object { members: Vec::new() }),
+            Event::Key => if let Some(Frame::Object { members }) = self.frames.last_mut() {
+                members.push((token.bytes, Vec::new()));
+            },
+            Event::EndObject => if let Some(Frame::Object { mut members }) = self.frames.pop() {
+                members.sort_by(|(a, _), (b, _)| a[1..a.len() - 1].cmp(&b[1..b.len() - 1]));
+
+                let mut bytes = vec![b'{'];
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i != 0 {
+                        bytes.push(b',');
+                    }
+                    bytes.extend_from_slice(key);
+                    bytes.push(b':');
+                    bytes.extend_from_slice(value);
+                }
+                bytes.push(b'}');
+
+                self.emit(&bytes);
+            },
+        }
+    }
+
+    /// Write the bytes in the value of the innermost object or in the output.
+    fn emit(&mut self, bytes: &[u8]) {
+        let object = self.frames.iter_mut().rev().find_map(|frame| match frame {
+            Frame::Object { members } => members.last_mut(),
+            Frame::Array { .. } => None,
+        });
+
+        match object {
+            Some((_, value)) => value.extend_from_slice(bytes),
+            None => self.output.extend_from_slice(bytes),
+        }
+    }
+}
+
+impl<R: io::Read> io::Read for SortingChecker<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let sorter = &mut self.sorter;
+
+        while self.pos == sorter.output.len() && !self.eof {
+            sorter.output.clear();
+            self.pos = 0;
+
+            let len = self.reader.read(&mut self.buffer)?;
+            if len == 0 {
+                self.eof = true;
+                self.scanner.end_of_input();
+            }
+
+            for byte in &self.buffer[..len] {
+                self.scanner.feed(*byte)?;
+                self.scanner.drain().for_each(|token| sorter.token(token));
+            }
+
+            self.scanner.drain().for_each(|token| sorter.token(token));
+        }
+
+        let output = &sorter.output[self.pos..];
+        let len = buf.len().min(output.len());
+        buf[..len].copy_from_slice(&output[..len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// Validate the JSON bytes and returns `true` if they are already in the canonical
+/// minified form, the form the `SortingChecker` outputs with canonical numbers.
+///
+/// The bytes must not contain whitespaces between the tokens, the keys of the objects
+/// must be sorted by their raw bytes and the numbers must not have an exponent,
+/// trailing zeros in their fraction or be a negative zero.
+///
+/// # Example
+///
+/// ```
+/// use oxidized_json_checker::is_canonical_minified;
+///
+/// assert_eq!(is_canonical_minified(br#"{"a":[1,2.5],"b":"c d"}"#), Ok(true));
+/// assert_eq!(is_canonical_minified(br#"{"b":1,"a":2}"#), Ok(false));
+/// assert_eq!(is_canonical_minified(br#"[1, 2]"#), Ok(false));
+/// assert_eq!(is_canonical_minified(br#"[1e3]"#), Ok(false));
+/// ```
+pub fn is_canonical_minified(bytes: &[u8]) -> Result<bool, Error> {
+    let mut scanner = Scanner::new(false);
+    // The span of the last key of every container, arrays included.
+    let mut last_keys: Vec<Option<Range<usize>>> = Vec::new();
+    let mut minified = true;
+    let mut canonical = true;
+
+    let mut check = |token: Token| {
+        let content = token.span.start + 1..token.span.end - 1;
+        match token.event {
+            Event::StartObject | Event::StartArray => last_keys.push(None),
+            Event::EndObject | Event::EndArray => {
+                last_keys.pop();
+            },
+            Event::Key => if let Some(last_key) = last_keys.last_mut() {
+                if let Some(last) = last_key.replace(content.clone()) {
+                    canonical &= bytes[last] <= bytes[content];
+                }
+            },
+            Event::Scalar(JsonType::Number) => canonical &= is_canonical_number(&bytes[token.span]),
+            Event::Scalar(_) => (),
+        }
+    };
+
+    for byte in bytes {
+        let state = scanner.state();
+        scanner.feed(*byte)?;
+        if state != State::St && matches!(byte, b' ' | b'\t' | b'\n' | b'\r') {
+            minified = false;
+        }
+        scanner.drain().for_each(&mut check);
+    }
+
+    scanner.end_of_input();
+    scanner.drain().for_each(&mut check);
+    scanner.finish()?;
+
+    Ok(minified && canonical)
+}
+
+/// Returns `true` if the number has no exponent, no trailing zeros
+/// in its fraction and is not a negative zero.
+fn is_canonical_number(number: &[u8]) -> bool {
+    let has_exponent = number.iter().any(|b| matches!(b, b'e' | b'E'));
+    let has_trailing_zero = number.contains(&b'.') && number.ends_with(b"0");
+    !has_exponent && !has_trailing_zero && number != b"-0"
+}
@@ -0,0 +1,194 @@
+use std::io;
+
+use crate::events::{for_each_byte, Event, Scanner, Token};
+use crate::internals::State;
+use crate::JsonType;
+
+/// Statistics about the content of a JSON document.
+///
+/// Returned by the `validate_with_stats` function.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of elements of the longest array of the document.
+    pub max_array_len_seen: usize,
+    /// The maximum number of direct children of the containers at each depth,
+    /// the root container being at depth 0.
+    pub level_widths: Vec<usize>,
+    /// The number of raw bytes between the quotes of the longest string, keys included.
+    pub max_string_len_seen: usize,
+    /// The number of arrays that contain values of different types.
+    pub mixed_type_arrays: u64,
+    /// The number of bytes that are not whitespaces between the tokens, this is the
+    /// size of the document once minified.
+    pub significant_bytes: u64,
+    /// The number of escape sequences of each kind in the strings, keys included.
+    pub escape_counts: EscapeCounts,
+    /// The number of strings, keys included, that contain non-ASCII
+    /// characters and must be escaped to produce an ASCII-only document.
+    pub strings_needing_escape_for_ascii: usize,
+    /// The number of values of each type at each depth, indexed by depth then by
+    /// `JsonType as usize`, the root value being at depth 0, keys are not counted.
+    pub type_by_depth: Vec<[u64; 6]>,
+}
+
+/// The number of escape sequences of each kind.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct EscapeCounts {
+    /// `\"`
+    pub quote: u64,
+    /// `\\`
+    pub backslash: u64,
+    /// `\/`
+    pub slash: u64,
+    /// `\b`
+    pub backspace: u64,
+    /// `\f`
+    pub form_feed: u64,
+    /// `\n`
+    pub newline: u64,
+    /// `\r`
+    pub carriage_return: u64,
+    /// `\t`
+    pub tab: u64,
+    /// `\uXXXX`
+    pub unicode: u64,
+}
+
+/// An array or an object along with the elements it contains.
+#[derive(Default)]
+struct Container {
+    is_array: bool,
+    len: usize,
+    first_type: Option<JsonType>,
+    mixed: bool,
+}
+
+#[derive(Default)]
+struct Collector {
+    stats: Stats,
+    containers: Vec<Container>,
+    non_ascii_string: bool,
+}
+
+impl Collector {
+    /// Called for every byte along with the state of the automaton before this byte.
+    fn byte(&mut self, state: State, byte: u8) {
+        let is_whitespace = matches!(byte, b' ' | b'\t' | b'\n' | b'\r');
+        if state == State::St || !is_whitespace {
+            self.stats.significant_bytes += 1;
+        }
+
+        if state == State::St && byte >= 128 {
+            self.non_ascii_string = true;
+        }
+
+        // The byte that follows a backslash gives the kind of escape sequence.
+        if state == State::Es {
+            let counts = &mut self.stats.escape_counts;
+            let count = match byte {
+                b'"' => &mut counts.quote,
+                b'\\' => &mut counts.backslash,
+                b'/' => &mut counts.slash,
+                b'b' => &mut counts.backspace,
+                b'f' => &mut counts.form_feed,
+                b'n' => &mut counts.newline,
+                b'r' => &mut counts.carriage_return,
+                b't' => &mut counts.tab,
+                _ => &mut counts.unicode,
+            };
+            *count += 1;
+        }
+    }
+
+    fn token(&mut self, token: Token) {
+        let value_type = match token.event {
+            Event::StartObject => Some(JsonType::Object),
+            Event::StartArray => Some(JsonType::Array),
+            Event::Scalar(json_type) => Some(json_type),
+            _ => None,
+        };
+
+        if let Some(value_type) = value_type {
+            let counts = &mut self.stats.type_by_depth;
+            if counts.len() <= token.depth {
+                counts.resize(token.depth + 1, [0; 6]);
+            }
+            counts[token.depth][value_type as usize] += 1;
+        }
+
+        if let (Some(value_type), Some(parent)) = (value_type, self.containers.last_mut()) {
+            parent.len += 1;
+            match parent.first_type {
+                Some(first_type) => parent.mixed |= first_type != value_type,
+                None => parent.first_type = Some(value_type),
+            }
+        }
+
+        if let Event::Key | Event::Scalar(JsonType::String) = token.event {
+            // The bytes are not captured, the span is enough to know the length.
+            let len = token.span.len() - 2;
+            self.stats.max_string_len_seen = self.stats.max_string_len_seen.max(len);
+
+            if std::mem::take(&mut self.non_ascii_string) {
+                self.stats.strings_needing_escape_for_ascii += 1;
+            }
+        }
+
+        match token.event {
+            Event::StartObject => self.containers.push(Container::default()),
+            Event::StartArray => self.containers.push(Container { is_array: true, ..Container::default() }),
+            Event::EndObject | Event::EndArray => {
+                let container = self.containers.pop().expect("BUG: unbalanced containers");
+                if container.is_array {
+                    let max = &mut self.stats.max_array_len_seen;
+                    *max = (*max).max(container.len);
+                    if container.mixed {
+                        self.stats.mixed_type_arrays += 1;
+                    }
+                }
+
+                let widths = &mut self.stats.level_widths;
+                if widths.len() <= token.depth {
+                    widths.resize(token.depth + 1, 0);
+                }
+                widths[token.depth] = widths[token.depth].max(container.len);
+            },
+            _ => (),
+        }
+    }
+}
+
+/// A convenient method to check JSON from a stream of bytes
+/// and gather statistics about its content.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use oxidized_json_checker::{validate_with_stats, JsonType};
+///
+/// let text = r#"{"a": [1, 2, 3], "b": [4, 5]}"#;
+/// let (json_type, stats) = validate_with_stats(text.as_bytes())?;
+/// assert_eq!(json_type, JsonType::Object);
+/// assert_eq!(stats.max_array_len_seen, 3);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn validate_with_stats<R: io::Read>(reader: R) -> io::Result<(JsonType, Stats)> {
+    let mut collector = Collector::default();
+    let mut scanner = Scanner::new(false);
+
+    for_each_byte(reader, |byte| {
+        let state = scanner.state();
+        scanner.feed(byte)?;
+        collector.byte(state, byte);
+        scanner.drain().for_each(|token| collector.token(token));
+        Ok(())
+    })?;
+
+    scanner.end_of_input();
+    scanner.drain().for_each(|token| collector.token(token));
+    let json_type = scanner.finish()?;
+
+    Ok((json_type, collector.stats))
+}
@@ -9,6 +9,11 @@ fn parse(text: &str) -> io::Result<JsonType> {
     Ok(outer_type)
 }
 
+fn parse_with(mut checker: JsonChecker<()>, text: &[u8]) -> Result<JsonType, Error> {
+    checker.next_bytes(text)?;
+    checker.finish()
+}
+
 #[test]
 fn it_works() {
     let json = r#"{"hello": "girls 😜 ❤️"}"#;
@@ -334,3 +339,989 @@ fn pass_3() {
 
     assert_eq!(outer_type, JsonType::Object);
 }
+
+#[test]
+fn status_broken_as_soon_as_possible() {
+    let mut checker = JsonChecker::new(&b"[1,x, 2, 3]"[..]);
+    let mut buf = [0; 1];
+
+    for _ in 0..3 {
+        checker.read_exact(&mut buf).unwrap();
+        assert_eq!(checker.status(), PrefixStatus::NeedMore);
+    }
+
+    // The `x` byte makes the document invalid...
+    checker.read_exact(&mut buf).unwrap_err();
+    assert_eq!(checker.status(), PrefixStatus::Broken(Error::InvalidState));
+
+    // ...and the checker is fused, further reads return the error.
+    checker.read_exact(&mut buf).unwrap_err();
+    assert_eq!(checker.status(), PrefixStatus::Broken(Error::InvalidState));
+}
+
+#[test]
+fn status_need_more_after_fraction_point() {
+    let mut checker = JsonChecker::new(());
+    checker.next_bytes(b"1.").unwrap();
+    assert_eq!(checker.status(), PrefixStatus::NeedMore);
+
+    checker.next_bytes(b"5").unwrap();
+    assert_eq!(checker.status(), PrefixStatus::Complete(JsonType::Number));
+}
+
+#[test]
+fn status_complete_with_trailing_whitespaces() {
+    let mut checker = JsonChecker::new(&b"{\"a\": 1}  \n"[..]);
+    checker.read_to_end(&mut Vec::new()).unwrap();
+    assert_eq!(checker.status(), PrefixStatus::Complete(JsonType::Object));
+}
+
+#[test]
+fn max_container_depth() {
+    assert_eq!(parse_with(JsonChecker::new(()).max_container_depth(2), br#"[[1]]"#), Ok(JsonType::Array));
+    assert_eq!(parse_with(JsonChecker::new(()).max_container_depth(2), br#"[{"a": "b"}]"#), Ok(JsonType::Array));
+    assert_eq!(parse_with(JsonChecker::new(()).max_container_depth(2), br#"[[[1]]]"#), Err(Error::MaxDepthReached));
+    assert_eq!(parse_with(JsonChecker::new(()).max_container_depth(2), br#"[{"a": [1]}]"#), Err(Error::MaxDepthReached));
+}
+
+#[test]
+fn validate_with_prefix_split() {
+    let (prefix, rest) = r#"{"a":1}"#.as_bytes().split_at(3);
+    assert_eq!(validate_with_prefix(prefix, rest).unwrap(), JsonType::Object);
+
+    let (prefix, rest) = r#"{"a":}"#.as_bytes().split_at(3);
+    assert!(validate_with_prefix(prefix, rest).is_err());
+}
+
+#[test]
+fn structural_signature_ignores_values() {
+    let signature = |text: &str| structural_signature(text.as_bytes()).unwrap();
+
+    assert_eq!(signature(r#"{"a":1}"#), signature(r#"{"b":2}"#));
+    assert_eq!(signature(r#"[1, "a", {"b": null}]"#), signature(r#"[ 2,"bc",{"d":null} ]"#));
+    assert_ne!(signature(r#"{"a":1}"#), signature(r#"[1]"#));
+    assert_ne!(signature(r#"{"a":1}"#), signature(r#"{"a":"1"}"#));
+    assert_ne!(signature(r#"[[1],2]"#), signature(r#"[[1,2]]"#));
+    assert!(structural_signature(&br#"{"a":}"#[..]).is_err());
+}
+
+#[test]
+#[cfg(feature = "internals")]
+fn step_through_array() {
+    use crate::internals::{Action, Mode, StepInfo};
+
+    let mut checker = JsonChecker::new(());
+    let steps: Vec<_> = b"[1]".iter().map(|b| checker.step(*b).unwrap()).collect();
+
+    assert_eq!(steps, vec![
+        StepInfo { state: State::Ar, action: Action::Push(Mode::Array), depth: 1 },
+        StepInfo { state: State::In, action: Action::None, depth: 1 },
+        StepInfo { state: State::Ok, action: Action::Pop(Mode::Array), depth: 0 },
+    ]);
+
+    let mut checker = JsonChecker::new(());
+    let steps: Vec<_> = br#"{"a":1}"#.iter().map(|b| checker.step(*b).unwrap().action).collect();
+
+    assert_eq!(steps, vec![
+        Action::Push(Mode::Key),
+        Action::None,
+        Action::None,
+        Action::None,
+        Action::Flip(Mode::Key, Mode::Object),
+        Action::None,
+        Action::Pop(Mode::Object),
+    ]);
+
+    checker.step(b'x').unwrap_err();
+    assert_eq!(checker.step(b' ').unwrap_err(), Error::InvalidState);
+}
+
+#[test]
+fn validate_optional_whitespaces() {
+    assert_eq!(validate_optional(b""), Ok(None));
+    assert_eq!(validate_optional(b" \t\r\n "), Ok(None));
+    assert_eq!(validate_optional(b" [1, 2] "), Ok(Some(JsonType::Array)));
+    assert_eq!(validate_optional(b"-"), Err(Error::IncompleteElement));
+    assert_eq!(validate_optional(b" x"), Err(Error::InvalidState));
+}
+
+#[test]
+fn first_field_value_of_objects() {
+    let value = first_field_value(br#"{"type":"user","id":5}"#).unwrap();
+    assert_eq!(value, Some((br#""user""#.to_vec(), JsonType::String)));
+
+    let value = first_field_value(br#"{ "a" : [1, {"b": 2}] , "c": 3 }"#).unwrap();
+    assert_eq!(value, Some((br#"[1, {"b": 2}]"#.to_vec(), JsonType::Array)));
+
+    let value = first_field_value(br#"{"a":12}"#).unwrap();
+    assert_eq!(value, Some((b"12".to_vec(), JsonType::Number)));
+
+    assert_eq!(first_field_value(br#"{}"#), Ok(None));
+    assert_eq!(first_field_value(br#"[{"a": 1}]"#), Ok(None));
+    assert!(first_field_value(br#"{"a": 1, "b"}"#).is_err());
+}
+
+#[test]
+fn validate_slice_range_offsets() {
+    let mut bytes = vec![0xff; 10];
+    bytes.extend_from_slice(br#"{"a":1}"#);
+    bytes.extend_from_slice(br#"{"a":x}"#);
+    bytes.extend_from_slice(b"\x00\x00");
+
+    assert_eq!(validate_slice_range(&bytes, 10..17), Ok(JsonType::Object));
+
+    let err = validate_slice_range(&bytes, 17..24).unwrap_err();
+    assert_eq!(err, DetailedError { error: Error::InvalidState, offset: 22, depth_at_error: 1 });
+
+    let err = validate_slice_range(&bytes, 10..16).unwrap_err();
+    assert_eq!(err, DetailedError { error: Error::IncompleteElement, offset: 16, depth_at_error: 1 });
+}
+
+#[test]
+fn stats_max_array_len_seen() {
+    let text = r#"{"a": [1, 2], "b": [[1, 2, 3, 4, 5], {"c": [1, 2, 3]}]}"#;
+    let (_, stats) = validate_with_stats(text.as_bytes()).unwrap();
+    assert_eq!(stats.max_array_len_seen, 5);
+
+    let (_, stats) = validate_with_stats(&br#"{"a": 1, "b": 2, "c": []}"#[..]).unwrap();
+    assert_eq!(stats.max_array_len_seen, 0);
+}
+
+#[test]
+fn stats_level_widths() {
+    let text = r#"[{"a": 1, "b": [1, 2, 3, 4]}, {"c": {"d": 1, "e": 2, "f": 3}}, []]"#;
+    let (_, stats) = validate_with_stats(text.as_bytes()).unwrap();
+    assert_eq!(stats.level_widths, vec![3, 2, 4]);
+
+    let (_, stats) = validate_with_stats(&b"42"[..]).unwrap();
+    assert!(stats.level_widths.is_empty());
+}
+
+#[test]
+fn non_ascii_structural() {
+    assert_eq!(validate_str(r#"{"a": é}"#), Err(Error::NonAsciiStructural));
+    assert_eq!(validate_bytes(b"[1, \xff]"), Err(Error::NonAsciiStructural));
+    assert_eq!(validate_bytes(b"\xef\xbb\xbf{}"), Err(Error::NonAsciiStructural));
+    assert_eq!(validate_str(r#"{"é": "😜"}"#), Ok(JsonType::Object));
+    assert_eq!(validate_bytes(b"[\"\\\xff\"]"), Err(Error::InvalidState));
+}
+
+#[test]
+fn validate_first_buffered_leftover() {
+    let (json_type, leftover, _, consumed) = validate_first_buffered(&br#"[1,2] {"a":1}"#[..]).unwrap();
+    assert_eq!(json_type, JsonType::Array);
+    assert_eq!(leftover, br#" {"a":1}"#);
+    assert!(!consumed);
+
+    let (json_type, leftover, _, consumed) = validate_first_buffered(&br#"12.5e3{"a":1}"#[..]).unwrap();
+    assert_eq!(json_type, JsonType::Number);
+    assert_eq!(leftover, br#"{"a":1}"#);
+    assert!(!consumed);
+
+    let (json_type, leftover, _, consumed) = validate_first_buffered(&b"true"[..]).unwrap();
+    assert_eq!(json_type, JsonType::Bool);
+    assert!(leftover.is_empty());
+    assert!(consumed);
+
+    assert!(validate_first_buffered(&b"[1, x] 2"[..]).is_err());
+
+    // A fraction point must be followed by a digit, it cannot end the value.
+    let err = validate_first_buffered(&b"1. x"[..]).unwrap_err();
+    assert_eq!(err.get_ref().and_then(|e| e.downcast_ref()), Some(&Error::InvalidState));
+}
+
+#[test]
+fn validate_first_buffered_consumed() {
+    // The whitespaces are split between two reads.
+    let reader = (&b"{\"a\": 1}  "[..]).chain(&b" \n "[..]);
+    let (json_type, leftover, _, consumed) = validate_first_buffered(reader).unwrap();
+    assert_eq!(json_type, JsonType::Object);
+    assert_eq!(leftover, b"   \n ");
+    assert!(consumed);
+
+    let reader = (&b"{\"a\": 1}  "[..]).chain(&b" [] "[..]);
+    let (_, leftover, mut reader, consumed) = validate_first_buffered(reader).unwrap();
+    assert_eq!(leftover, b"   [] ");
+    assert!(!consumed);
+    assert_eq!(reader.read(&mut [0; 8]).unwrap(), 0);
+}
+
+#[test]
+fn depth_element_limits() {
+    let elements: Vec<_> = (0..1000).map(|i| i.to_string()).collect();
+    let text = format!("[{}]", elements.join(","));
+    assert_eq!(parse_with(JsonChecker::new(()).depth_element_limits(vec![1000, 10]), text.as_bytes()), Ok(JsonType::Array));
+
+    let text = r#"[[1,2,3,4,5,6,7,8,9,10]]"#;
+    assert_eq!(parse_with(JsonChecker::new(()).depth_element_limits(vec![1000, 10]), text.as_bytes()), Ok(JsonType::Array));
+
+    let text = r#"[[1,2,3,4,5,6,7,8,9,10,11]]"#;
+    let mut checker = JsonChecker::new(()).depth_element_limits(vec![1000, 10]);
+    let err = checker.next_bytes(text.as_bytes()).unwrap_err();
+    assert_eq!(err, Error::DepthElementLimitExceeded { depth: 1 });
+    assert_eq!(checker.offset(), text.find("11").unwrap());
+
+    // The last limit applies to the deeper containers.
+    let text = r#"{"a": {"b": [1, 2, 3]}}"#;
+    assert_eq!(parse_with(JsonChecker::new(()).depth_element_limits(vec![1, 2]), text.as_bytes()), Err(Error::DepthElementLimitExceeded { depth: 2 }));
+}
+
+#[test]
+fn key_bloom_membership() {
+    let text = r#"{"name": "Kero", "links": [{"href": "a", "rel": "self"}], "se\"cret": null}"#;
+    let (json_type, bloom) = validate_with_key_bloom(text.as_bytes(), KeyBloom::default()).unwrap();
+    assert_eq!(json_type, JsonType::Object);
+
+    for key in &[&b"name"[..], b"links", b"href", b"rel", br#"se\"cret"#] {
+        assert!(bloom.contains(key));
+    }
+    assert!(!bloom.contains(b"absent"));
+    assert!(!bloom.contains(b"Kero"));
+
+    assert!(validate_with_key_bloom(&br#"{"a" 1}"#[..], KeyBloom::default()).is_err());
+}
+
+#[test]
+fn leading_zeros() {
+    assert_eq!(parse_with(JsonChecker::new(()).allow_leading_zeros(false), b"013"), Err(Error::InvalidState));
+    assert_eq!(parse_with(JsonChecker::new(()).allow_leading_zeros(false), b"[-013]"), Err(Error::InvalidState));
+    assert_eq!(parse_with(JsonChecker::new(()).allow_leading_zeros(true), b"013"), Ok(JsonType::Number));
+    assert_eq!(parse_with(JsonChecker::new(()).allow_leading_zeros(true), br#"{"a": -0013.5e2}"#), Ok(JsonType::Object));
+
+    assert_eq!(validate_str("0"), Ok(JsonType::Number));
+    assert_eq!(validate_str("-0"), Ok(JsonType::Number));
+    assert_eq!(validate_str("0.5"), Ok(JsonType::Number));
+}
+
+#[test]
+fn stats_max_string_len_seen() {
+    let text = r#"{"key": "value", "a longer key": ["the longest \"string\"", 12345678901234567890]}"#;
+    let (_, stats) = validate_with_stats(text.as_bytes()).unwrap();
+    assert_eq!(stats.max_string_len_seen, r#"the longest \"string\""#.len());
+
+    let (_, stats) = validate_with_stats(&br#"["", 1]"#[..]).unwrap();
+    assert_eq!(stats.max_string_len_seen, 0);
+}
+
+#[test]
+fn validate_dir_results() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("ojc-validate-dir-{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("valid.json"), r#"{"a": [1, 2]}"#).unwrap();
+    fs::write(dir.join("invalid.json"), r#"{"a": [1, 2}"#).unwrap();
+    fs::write(dir.join("notes.txt"), "not json").unwrap();
+    fs::write(dir.join("sub").join("nested.json"), "42").unwrap();
+
+    let results = validate_dir(&dir, false);
+    let paths: Vec<_> = results.iter().map(|(p, _)| p.strip_prefix(&dir).unwrap().to_owned()).collect();
+    assert_eq!(paths, vec![Path::new("invalid.json"), Path::new("valid.json")]);
+    assert!(results[0].1.is_err());
+    assert_eq!(results[1].1.as_ref().unwrap(), &JsonType::Object);
+
+    let results = validate_dir(&dir, true);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[1].0, dir.join("sub").join("nested.json"));
+    assert_eq!(results[1].1.as_ref().unwrap(), &JsonType::Number);
+
+    let results = validate_dir(dir.join("missing"), true);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].1.is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn stats_mixed_type_arrays() {
+    let mixed = |text: &str| validate_with_stats(text.as_bytes()).unwrap().1.mixed_type_arrays;
+
+    assert_eq!(mixed(r#"[1,2,3]"#), 0);
+    assert_eq!(mixed(r#"[1,"two",3]"#), 1);
+    assert_eq!(mixed(r#"[[1, 2], [true, null], {"a": [{}, []]}]"#), 3);
+    assert_eq!(mixed(r#"{"a": 1, "b": "two"}"#), 0);
+}
+
+#[test]
+fn stats_significant_bytes() {
+    let minified = r#"{"a b":[1,2.5,"c d"],"e":{"f":null}}"#;
+    let pretty = r#"
+    {
+        "a b": [ 1, 2.5, "c d" ],
+        "e": {
+            "f": null
+        }
+    }
+    "#;
+
+    let (_, minified_stats) = validate_with_stats(minified.as_bytes()).unwrap();
+    let (_, pretty_stats) = validate_with_stats(pretty.as_bytes()).unwrap();
+    assert_eq!(minified_stats.significant_bytes, minified.len() as u64);
+    assert_eq!(pretty_stats.significant_bytes, minified.len() as u64);
+}
+
+#[test]
+fn max_total_escapes() {
+    // No string contains more than two escapes but there are six of them.
+    let text = r#"["a\nb\t", "\"c\"", {"é\\": "\u00e9"}]"#;
+    assert_eq!(parse_with(JsonChecker::new(()).max_total_escapes(6), text.as_bytes()), Ok(JsonType::Array));
+    assert_eq!(parse_with(JsonChecker::new(()).max_total_escapes(5), text.as_bytes()), Err(Error::TooManyEscapes));
+    assert_eq!(parse_with(JsonChecker::new(()).max_total_escapes(0), br#""no escapes""#), Ok(JsonType::String));
+}
+
+#[test]
+fn validate_root_in_allowed_types() {
+    let allowed = [JsonType::Object, JsonType::Array];
+    assert_eq!(validate_root_in(&br#"{"a": 1}"#[..], &allowed).unwrap(), JsonType::Object);
+    assert_eq!(validate_root_in(&b" [1, 2]"[..], &allowed).unwrap(), JsonType::Array);
+    assert!(validate_root_in(&b"42"[..], &allowed).is_err());
+
+    // The string is rejected on its first byte.
+    let mut checker = JsonChecker::new(()).allowed_root_types(&allowed);
+    let err = checker.feed(br#"  "a long string""#).unwrap_err();
+    let expected = Error::UnexpectedRootType { found: JsonType::String, allowed: JsonTypeSet::from(&allowed[..]) };
+    assert_eq!(err, expected);
+    assert_eq!(checker.offset(), 2);
+    assert_eq!(err.to_string(), "unexpected root type String, expected Array or Object");
+}
+
+#[test]
+fn depth_at_error() {
+    let text = br#"{"a":{"b":[1,x]}}"#;
+    let err = validate_slice_range(text, 0..text.len()).unwrap_err();
+    assert_eq!(err.error, Error::InvalidState);
+    assert_eq!(err.offset, 13);
+    assert_eq!(err.depth_at_error, 3);
+
+    // The failing pop doesn't change the reported depth.
+    let text = br#"[[1]}"#;
+    let err = validate_slice_range(text, 0..text.len()).unwrap_err();
+    assert_eq!(err.error, Error::OrphanCurlyBrace);
+    assert_eq!(err.depth_at_error, 1);
+
+    let err = validate_slice_range(b"  ", 0..2).unwrap_err();
+    assert_eq!(err.depth_at_error, 0);
+}
+
+#[test]
+fn duplicate_elements() {
+    assert!(has_duplicate_elements("[1,2,1]".as_bytes()).unwrap());
+    assert!(!has_duplicate_elements("[1,2,3]".as_bytes()).unwrap());
+    assert!(has_duplicate_elements(r#"["a","a"]"#.as_bytes()).unwrap());
+    assert!(!has_duplicate_elements(r#"[{"a":1},{"a":1},[1]]"#.as_bytes()).unwrap());
+    assert!(!has_duplicate_elements("1".as_bytes()).unwrap());
+    assert!(has_duplicate_elements("[1,2,1".as_bytes()).is_err());
+}
+
+#[test]
+fn string_spans_exclude_quotes() {
+    let text = r#"{"a":"secret"}"#;
+    let spans = string_spans(text.as_bytes()).unwrap();
+    assert_eq!(spans, vec![2..3, 6..12]);
+    assert_eq!(&text[spans[0].clone()], "a");
+    assert_eq!(&text[spans[1].clone()], "secret");
+
+    let text = r#"[["", "\"x"], {"b": [1, "c"]}]"#;
+    let spans = string_spans(text.as_bytes()).unwrap();
+    let strings: Vec<_> = spans.into_iter().map(|s| &text[s]).collect();
+    assert_eq!(strings, vec!["", r#"\"x"#, "b", "c"]);
+}
+
+#[test]
+fn deep_nesting_does_not_overflow() {
+    const LEVELS: usize = 1_000_000;
+
+    // We use a small thread stack to catch any recursion introduced in the automaton.
+    let handle = std::thread::Builder::new().stack_size(64 * 1024).spawn(|| {
+        let mut text = Vec::with_capacity(LEVELS * 6);
+        text.extend(b"[".repeat(LEVELS));
+        text.extend(b"]".repeat(LEVELS));
+
+        let mut checker = JsonChecker::with_max_depth(&text[..], LEVELS + 2);
+        io::copy(&mut checker, &mut io::sink()).unwrap();
+        assert_eq!(checker.finish().unwrap(), JsonType::Array);
+
+        let (_, stats) = validate_with_stats(&text[..]).unwrap();
+        assert_eq!(stats.level_widths.len(), LEVELS);
+        structural_signature(&text[..]).unwrap();
+
+        text.clear();
+        text.extend(br#"{"a":"#.repeat(LEVELS));
+        text.extend(b"null");
+        text.extend(b"}".repeat(LEVELS));
+        assert_eq!(validate_bytes(&text).unwrap(), JsonType::Object);
+    }).unwrap();
+
+    handle.join().unwrap();
+}
+
+#[test]
+fn value_end_offsets() {
+    assert_eq!(value_end_offset(b"[1,2,3]   ").unwrap(), (JsonType::Array, 7));
+    assert_eq!(value_end_offset(b"42 ").unwrap(), (JsonType::Number, 2));
+    assert_eq!(value_end_offset(b"42").unwrap(), (JsonType::Number, 2));
+    assert_eq!(value_end_offset(b" \"a\"\n").unwrap(), (JsonType::String, 4));
+    assert_eq!(value_end_offset(br#"{"a":[]} "#).unwrap(), (JsonType::Object, 8));
+    assert_eq!(value_end_offset(b"[1,2,3] 4"), Err(Error::InvalidState));
+}
+
+#[test]
+fn json_events_iterator() {
+    let text = r#"{"a": [1, true, "x"], "b": {"c": null}} "#;
+    let events: Vec<_> = JsonEvents::new(text.as_bytes()).map(Result::unwrap).collect();
+    assert_eq!(events, vec![
+        JsonEvent::StartObject,
+        JsonEvent::Key(br#""a""#.to_vec()),
+        JsonEvent::StartArray,
+        JsonEvent::Scalar(JsonType::Number, b"1".to_vec()),
+        JsonEvent::Scalar(JsonType::Bool, b"true".to_vec()),
+        JsonEvent::Scalar(JsonType::String, br#""x""#.to_vec()),
+        JsonEvent::EndArray,
+        JsonEvent::Key(br#""b""#.to_vec()),
+        JsonEvent::StartObject,
+        JsonEvent::Key(br#""c""#.to_vec()),
+        JsonEvent::Scalar(JsonType::Null, b"null".to_vec()),
+        JsonEvent::EndObject,
+        JsonEvent::EndObject,
+    ]);
+
+    let mut events = JsonEvents::new("-12.5".as_bytes());
+    assert_eq!(events.next().unwrap().unwrap(), JsonEvent::Scalar(JsonType::Number, b"-12.5".to_vec()));
+    assert!(events.next().is_none());
+
+    // The iterator is fused after an error.
+    let mut events = JsonEvents::new("[1, x]".as_bytes());
+    assert_eq!(events.next().unwrap().unwrap(), JsonEvent::StartArray);
+    assert_eq!(events.next().unwrap().unwrap(), JsonEvent::Scalar(JsonType::Number, b"1".to_vec()));
+    assert!(events.next().unwrap().is_err());
+    assert!(events.next().is_none());
+
+    let mut events = JsonEvents::new("[1".as_bytes());
+    assert_eq!(events.next().unwrap().unwrap(), JsonEvent::StartArray);
+    assert_eq!(events.next().unwrap().unwrap(), JsonEvent::Scalar(JsonType::Number, b"1".to_vec()));
+    assert!(events.next().unwrap().is_err());
+    assert!(events.next().is_none());
+}
+
+#[test]
+fn max_total_string_bytes() {
+    // Twelve bytes of strings, keys and escapes included.
+    let text = r#"{"ab": ["cde", "f\n", "ghij"]}"#;
+    assert_eq!(parse_with(JsonChecker::new(()).max_total_string_bytes(12), text.as_bytes()), Ok(JsonType::Object));
+    assert_eq!(parse_with(JsonChecker::new(()).max_total_string_bytes(11), text.as_bytes()), Err(Error::TotalStringBytesExceeded));
+    assert_eq!(parse_with(JsonChecker::new(()).max_total_string_bytes(0), br#"["", ""]"#), Ok(JsonType::Array));
+
+    // Long plain runs of bytes are counted, even with the `nightly` feature.
+    let text = r#"["abcdefghijklmnopqrstuvwxyz0123456789", "abcdefghijklmnop"]"#;
+    assert_eq!(parse_with(JsonChecker::new(()).max_total_string_bytes(52), text.as_bytes()), Ok(JsonType::Array));
+    assert_eq!(parse_with(JsonChecker::new(()).max_total_string_bytes(51), text.as_bytes()), Err(Error::TotalStringBytesExceeded));
+}
+
+#[test]
+fn array_preview_stops_early() {
+    use JsonType::*;
+
+    let text = r#"[1,"two",true,null,{}]"#;
+    assert_eq!(array_preview(text.as_bytes(), 3).unwrap(), vec![Number, String, Bool]);
+    assert_eq!(array_preview(text.as_bytes(), 10).unwrap(), vec![Number, String, Bool, Null, Object]);
+
+    // The remaining bytes are not read nor validated.
+    let text = r#"[[1, 2], 3.5, garbage"#;
+    assert_eq!(array_preview(text.as_bytes(), 2).unwrap(), vec![Array, Number]);
+    assert!(array_preview(text.as_bytes(), 3).is_err());
+
+    assert_eq!(array_preview("[]".as_bytes(), 2).unwrap(), vec![]);
+    assert!(array_preview("{}".as_bytes(), 2).is_err());
+}
+
+#[test]
+fn trailing_characters() {
+    assert_eq!(validate_str(r#"{"a":1} garbage"#), Err(Error::TrailingCharacters { offset: 8 }));
+    assert_eq!(validate_str("[1]]"), Err(Error::TrailingCharacters { offset: 3 }));
+    assert_eq!(validate_str("12 ,"), Err(Error::TrailingCharacters { offset: 3 }));
+    assert_eq!(validate_str("12x"), Err(Error::TrailingCharacters { offset: 2 }));
+    assert_eq!(validate_str("true false"), Err(Error::TrailingCharacters { offset: 5 }));
+    assert_eq!(validate_str(r#"{"a":1 x}"#), Err(Error::InvalidState));
+    assert_eq!(validate_str("[1] \n"), Ok(JsonType::Array));
+
+    // A fraction point is not the end of a value, the next byte is not trailing.
+    assert_eq!(validate_str("[1.]"), Err(Error::InvalidState));
+    assert_eq!(validate_bytes(b"1.x"), Err(Error::InvalidState));
+}
+
+#[test]
+fn type_limits() {
+    let limits = TypeLimits::new().max(JsonType::Object, 2);
+    assert_eq!(parse_with(JsonChecker::new(()).type_limits(limits), br#"[{}, {"a": 1}, 2, 3]"#), Ok(JsonType::Array));
+    assert_eq!(parse_with(JsonChecker::new(()).type_limits(limits), br#"[{}, {"a": {}}]"#), Err(Error::TypeLimitExceeded { ty: JsonType::Object }));
+    assert_eq!(parse_with(JsonChecker::new(()).type_limits(limits), br#"{"a": {}, "b": {}}"#), Err(Error::TypeLimitExceeded { ty: JsonType::Object }));
+
+    let limits = TypeLimits::new().max(JsonType::String, 1).max(JsonType::Null, 0);
+    assert_eq!(parse_with(JsonChecker::new(()).type_limits(limits), br#"{"a": "b"}"#), Ok(JsonType::Object));
+    assert_eq!(parse_with(JsonChecker::new(()).type_limits(limits), br#"["a", "b"]"#), Err(Error::TypeLimitExceeded { ty: JsonType::String }));
+    assert_eq!(parse_with(JsonChecker::new(()).type_limits(limits), b"[null]"), Err(Error::TypeLimitExceeded { ty: JsonType::Null }));
+}
+
+#[test]
+fn completeness() {
+    use Completeness::*;
+
+    assert_eq!(validate_completeness(br#"{"a":"#), (Some(JsonType::Object), Incomplete));
+    assert_eq!(validate_completeness(br#"{"a":1}"#), (Some(JsonType::Object), Complete));
+    assert_eq!(validate_completeness(br#"{"a":,}"#), (Some(JsonType::Object), Invalid));
+    assert_eq!(validate_completeness(b"  "), (None, Incomplete));
+    assert_eq!(validate_completeness(b"12"), (Some(JsonType::Number), Complete));
+    assert_eq!(validate_completeness(b"x"), (None, Invalid));
+    assert_eq!(validate_completeness(b"1."), (Some(JsonType::Number), Incomplete));
+}
+
+#[test]
+fn max_integer_magnitude() {
+    let max = i64::MAX as u64;
+    assert_eq!(parse_with(JsonChecker::new(()).max_integer_magnitude(max), b"9223372036854775808"), Err(Error::IntegerOutOfRange));
+    assert_eq!(parse_with(JsonChecker::new(()).max_integer_magnitude(max), b"9223372036854775807"), Ok(JsonType::Number));
+    assert_eq!(parse_with(JsonChecker::new(()).max_integer_magnitude(max), b"[-9223372036854775808]"), Err(Error::IntegerOutOfRange));
+    assert_eq!(parse_with(JsonChecker::new(()).max_integer_magnitude(max), b"[1, 99999999999999999999999]"), Err(Error::IntegerOutOfRange));
+    assert_eq!(parse_with(JsonChecker::new(()).max_integer_magnitude(u64::MAX), b"99999999999999999999999"), Err(Error::IntegerOutOfRange));
+    assert_eq!(parse_with(JsonChecker::new(()).max_integer_magnitude(max), b"1.5e300"), Ok(JsonType::Number));
+    assert_eq!(parse_with(JsonChecker::new(()).max_integer_magnitude(max), br#"{"a": 99999999999999999999.0}"#), Ok(JsonType::Object));
+    assert_eq!(parse_with(JsonChecker::new(()).max_integer_magnitude(100), br#"{"a": 0, "b": 100}"#), Ok(JsonType::Object));
+    assert_eq!(parse_with(JsonChecker::new(()).max_integer_magnitude(100), br#"{"a": 0, "b": 101}"#), Err(Error::IntegerOutOfRange));
+
+    // The status does not wait for the end of the integer to report it.
+    let mut checker = JsonChecker::new(()).max_integer_magnitude(10);
+    checker.next_bytes(b"10").unwrap();
+    assert_eq!(checker.status(), PrefixStatus::Complete(JsonType::Number));
+    checker.next_bytes(b"1").unwrap();
+    assert_eq!(checker.status(), PrefixStatus::Broken(Error::IntegerOutOfRange));
+    assert_eq!(checker.finish(), Err(Error::IntegerOutOfRange));
+}
+
+#[test]
+fn validate_span_positions() {
+    let (json_type, start, end) = validate_span(b"\n  [1,2]\n").unwrap();
+    assert_eq!(json_type, JsonType::Array);
+    assert_eq!(start, Position { offset: 3, line: 2, column: 3 });
+    assert_eq!(end, Position { offset: 7, line: 2, column: 7 });
+
+    let (_, start, end) = validate_span(b" 42 ").unwrap();
+    assert_eq!(start, Position { offset: 1, line: 1, column: 2 });
+    assert_eq!(end, Position { offset: 2, line: 1, column: 3 });
+
+    let (_, start, end) = validate_span(b"{\"a\":\n[\n]}").unwrap();
+    assert_eq!(start.to_string(), "1:1");
+    assert_eq!(end.to_string(), "3:2");
+
+    assert!(validate_span(b" ").is_err());
+}
+
+#[test]
+fn stats_escape_counts() {
+    let text = r#"{"\t": "\n\t\"\\\/\b\f\r\u00e9\uD83D\uDE00", "a": "\n"}"#;
+    let (_, stats) = validate_with_stats(text.as_bytes()).unwrap();
+    let expected = EscapeCounts {
+        quote: 1,
+        backslash: 1,
+        slash: 1,
+        backspace: 1,
+        form_feed: 1,
+        newline: 2,
+        carriage_return: 1,
+        tab: 2,
+        unicode: 3,
+    };
+    assert_eq!(stats.escape_counts, expected);
+}
+
+#[test]
+fn container_callbacks() {
+    use std::sync::mpsc;
+
+    let (sender, receiver) = mpsc::channel();
+    let close_sender = sender.clone();
+    let mut checker = JsonChecker::new(())
+        .on_open(move |ty, depth| sender.send(("open", ty, depth)).unwrap())
+        .on_close(move |ty, depth| close_sender.send(("close", ty, depth)).unwrap());
+
+    checker.next_bytes(br#"{"a":[1,{}]}"#).unwrap();
+    checker.finish().unwrap();
+
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert_eq!(events, vec![
+        ("open", JsonType::Object, 0),
+        ("open", JsonType::Array, 1),
+        ("open", JsonType::Object, 2),
+        ("close", JsonType::Object, 2),
+        ("close", JsonType::Array, 1),
+        ("close", JsonType::Object, 0),
+    ]);
+}
+
+#[test]
+fn checker_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<JsonChecker<&[u8]>>();
+}
+
+#[test]
+fn validate_str_multi_byte_strings() {
+    let text = r#"{"clé": ["é", "日本語", "😜 ❤️", "\u00e9"]}"#;
+    assert_eq!(validate_str(text), Ok(JsonType::Object));
+    assert_eq!(validate_str("\"\u{10FFFF}\""), Ok(JsonType::String));
+
+    // The bytes are not decoded, invalid UTF-8 sequences in strings go through.
+    assert_eq!(validate_bytes(b"\"\xff\xfe\""), Ok(JsonType::String));
+}
+
+#[test]
+fn find_value_pointer() {
+    let find = |text: &str, needle: &[u8]| find_value(text.as_bytes(), needle).unwrap();
+
+    assert_eq!(find(r#"{"a":[1,42]}"#, b"42").as_deref(), Some("/a/1"));
+    assert_eq!(find(r#"{"a":[1,42]}"#, b"43"), None);
+    assert_eq!(find("42", b"42").as_deref(), Some(""));
+    assert_eq!(find(r#"[{}, [], {"a/b": {"c~": [null, "x"]}}]"#, br#""x""#).as_deref(), Some("/2/a~1b/c~0/1"));
+    assert_eq!(find(r#"{"a": {"x": 42}, "b": 42}"#, b"42").as_deref(), Some("/a/x"));
+    assert_eq!(find(r#"{"a": "x"}"#, b"x"), None);
+    assert!(find_value(r#"[42, "#.as_bytes(), b"42").is_err());
+}
+
+#[test]
+fn pointer_decodes_keys() {
+    let find = |text: &str| find_value(text.as_bytes(), b"1").unwrap();
+
+    assert_eq!(find(r#"{"a\/b": 1}"#).as_deref(), Some("/a~1b"));
+    assert_eq!(find(r#"{"a\"b": 1}"#).as_deref(), Some("/a\"b"));
+    assert_eq!(find(r#"{"a\\b": 1}"#).as_deref(), Some("/a\\b"));
+    assert_eq!(find(r#"{"\u007e\u002F": 1}"#).as_deref(), Some("/~0~1"));
+    assert_eq!(find(r#"{"cl\u00e9": {"\ud83d\ude1c": [1]}}"#).as_deref(), Some("/clé/😜/0"));
+    assert_eq!(find(r#"{"\ud83d": 1}"#).as_deref(), Some("/\u{fffd}"));
+    assert_eq!(find_value(&b"{\"a\xff\": 1}"[..], b"1").unwrap().as_deref(), Some("/a\u{fffd}"));
+
+    let mut pointers = Vec::new();
+    flatten(r#"{"a\tb": [1], "c\/d": 2}"#.as_bytes(), |pointer, _, _| pointers.push(pointer)).unwrap();
+    assert_eq!(pointers, vec!["/a\tb/0", "/c~1d"]);
+
+    let tree = merkle_tree(&br#"{"x\/y": {}}"#[..]).unwrap();
+    assert_eq!(tree.subtrees[0].0, "/x~1y");
+}
+
+#[test]
+fn key_charset() {
+    let charset = |c: char| c.is_alphanumeric() || c == '_';
+
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"valid_key":1}"#), Ok(JsonType::Object));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"has space":1}"#), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"a": "values are not checked", "b": {"c d": 1}}"#), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"["not a key"]"#), Ok(JsonType::Array));
+
+    // The multi-bytes characters and escape sequences are decoded.
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), r#"{"clé": 1, "\u00e9t\u00C9": 2, "\uD835\uDC00": 3}"#.as_bytes()), Ok(JsonType::Object));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"a\nb": 1}"#), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"a\u002Db": 1}"#), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), r#"{"😜": 1}"#.as_bytes()), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"\uD835": 1}"#), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"\uD835a": 1}"#), Err(Error::InvalidKeyCharacter));
+
+    // Long plain runs of bytes are checked, even with the `nightly` feature.
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"abcdefghijklmnopqrstuvwxyz": 1}"#), Ok(JsonType::Object));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"abcdefghijklmnop qrstuvwxyz": 1}"#), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(charset), br#"{"abcdefgh-ijklmnopqrstuvwxyz": 1}"#), Err(Error::InvalidKeyCharacter));
+
+    // The truncated multi-bytes sequences are invalid, whatever the predicate is.
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(|_| true), b"{\"a\xc3\": 1}"), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(|_| true), b"{\"\xe6\x97a\": 1}"), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(|_| true), b"{\"\xe6\x97\\n\": 1}"), Err(Error::InvalidKeyCharacter));
+    assert_eq!(parse_with(JsonChecker::new(()).key_charset(|_| true), b"{\"\xc3\xa9\": 1, \"\xe6\x97\xa5\": 2}"), Ok(JsonType::Object));
+}
+
+#[test]
+fn null_counts() {
+    let counts = null_counts_by_key(r#"[{"a":null},{"a":1,"b":null}]"#.as_bytes()).unwrap();
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort();
+    assert_eq!(counts, vec![(b"a".to_vec(), 1), (b"b".to_vec(), 1)]);
+
+    // Only the keys of the objects of the root array are counted.
+    let text = r#"[{"a": null, "b": {"c": null}}, [null], null, {"a": null}]"#;
+    let counts = null_counts_by_key(text.as_bytes()).unwrap();
+    assert_eq!(counts.len(), 1);
+    assert_eq!(counts[&b"a"[..]], 2);
+
+    assert!(null_counts_by_key(r#"{"a": null}"#.as_bytes()).unwrap().is_empty());
+}
+
+#[test]
+fn validate_with_snippet_window() {
+    let text = br#"[1, 2, 3, 4, 5, 6, x, 7, 8, 9]"#;
+    let (error, offset, snippet) = validate_with_snippet(text, 3).unwrap_err();
+    assert_eq!((error, offset), (Error::InvalidState, 19));
+    assert_eq!(snippet, b"6, x, 7");
+    assert_eq!(snippet[3], text[offset]);
+
+    // The window is truncated at the bounds of the input.
+    let (_, offset, snippet) = validate_with_snippet(b"x", 20).unwrap_err();
+    assert_eq!((offset, snippet), (0, b"x".to_vec()));
+
+    let (error, offset, snippet) = validate_with_snippet(b"[1, 2", 2).unwrap_err();
+    assert_eq!((error, offset), (Error::IncompleteElement, 5));
+    assert_eq!(snippet, b" 2");
+
+    assert_eq!(validate_with_snippet(b"[1, 2]", 2), Ok(JsonType::Array));
+}
+
+#[test]
+fn validate_batch_results() {
+    let buffers = vec![&br#"{"a": 1}"#[..], b"[1, 2", b"  42 "];
+    let results: Vec<_> = validate_batch(buffers).collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), &JsonType::Object);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap(), &JsonType::Number);
+}
+
+#[test]
+fn reset_keeps_configuration() {
+    let mut checker = JsonChecker::new(()).max_container_depth(1);
+    assert_eq!(checker.next_bytes(b"[[1]]"), Err(Error::MaxDepthReached));
+
+    checker.reset();
+    checker.next_bytes(b"[1, 2]").unwrap();
+    assert_eq!(checker.status(), PrefixStatus::Complete(JsonType::Array));
+
+    checker.reset();
+    assert_eq!(checker.next_bytes(b"[[1]]"), Err(Error::MaxDepthReached));
+
+    checker.reset();
+    checker.next_bytes(b"\"a\"").unwrap();
+    assert_eq!(checker.offset(), 3);
+    assert_eq!(checker.finish(), Ok(JsonType::String));
+}
+
+#[test]
+fn repeated_subtrees_count() {
+    let count = |text: &str| repeated_subtrees(text.as_bytes()).unwrap();
+
+    assert_eq!(count(r#"{"x": {"a": {"b": 1}}, "y": {"a": {"b": 1}}}"#), 2);
+    assert_eq!(count(r#"{"x": {"a": {"b": 1}}, "y": {"a": {"b": 2}}}"#), 0);
+    assert_eq!(count(r#"[[1, 2], [12], [1, 2], [1, 2]]"#), 2);
+    assert_eq!(count(r#"[{"a": 1}, {"a": "1"}, ["a", 1]]"#), 0);
+    assert_eq!(count(r#"[{}, []]"#), 0);
+    assert_eq!(count("42"), 0);
+}
+
+#[test]
+fn string_policy() {
+    let text = br#"{"a": "short", "b": ["very \"long\" string", "\u00e9\u00e9\u00e9"]}"#;
+
+    let mut checker = JsonChecker::new(()).max_string_len(8);
+    assert_eq!(checker.next_bytes(text), Err(Error::StringTooLong));
+
+    let mut checker = JsonChecker::new(()).max_string_len(8).string_policy(StringPolicy::Skip);
+    checker.next_bytes(text).unwrap();
+    assert_eq!(checker.truncated_strings(), 2);
+    assert_eq!(checker.finish(), Ok(JsonType::Object));
+
+    // The skipped strings are still validated.
+    let mut checker = JsonChecker::new(()).max_string_len(2).string_policy(StringPolicy::Skip);
+    assert_eq!(checker.next_bytes(br#"["abc\x"]"#), Err(Error::InvalidState));
+
+    let mut checker = JsonChecker::new(()).max_string_len(5);
+    checker.next_bytes(br#"{"abcde": "fghij"}"#).unwrap();
+    assert_eq!(checker.truncated_strings(), 0);
+    assert_eq!(checker.finish(), Ok(JsonType::Object));
+
+    // Long plain runs of bytes are counted, even with the `nightly` feature.
+    let text = br#"["abcdefghijklmnopqrstuvwxyz0123456789", "abcdefghijklmnop"]"#;
+    let mut checker = JsonChecker::new(()).max_string_len(35);
+    assert_eq!(checker.next_bytes(text), Err(Error::StringTooLong));
+
+    let mut checker = JsonChecker::new(()).max_string_len(16).string_policy(StringPolicy::Skip);
+    checker.next_bytes(text).unwrap();
+    assert_eq!(checker.truncated_strings(), 1);
+}
+
+#[test]
+fn sorting_checker() {
+    fn sort(text: &str) -> io::Result<(String, JsonType)> {
+        let mut checker = SortingChecker::new(text.as_bytes());
+        let mut output = String::new();
+        checker.read_to_string(&mut output)?;
+        Ok((output, checker.finish()?))
+    }
+
+    assert_eq!(sort(r#"{"b":1,"a":2}"#).unwrap(), (r#"{"a":2,"b":1}"#.to_string(), JsonType::Object));
+
+    let text = r#" { "b" : { "d" : [ 1 , { "f" : null , "e" : true } ] , "c" : "x" } , "a b" : 2.5e3 , "a" : [ ] } "#;
+    let expected = r#"{"a":[],"a b":2.5e3,"b":{"c":"x","d":[1,{"e":true,"f":null}]}}"#;
+    assert_eq!(sort(text).unwrap(), (expected.to_string(), JsonType::Object));
+
+    assert_eq!(sort(r#"[ {"y":1,"x":2} , 3 ]"#).unwrap(), (r#"[{"x":2,"y":1},3]"#.to_string(), JsonType::Array));
+    assert_eq!(sort(" 12 ").unwrap(), ("12".to_string(), JsonType::Number));
+    assert_eq!(sort(r#"{"a":2,"a":1}"#).unwrap().0, r#"{"a":2,"a":1}"#);
+    assert!(sort(r#"{"b":1,"a":}"#).is_err());
+    assert!(sort(r#"{"b":1"#).is_err());
+}
+
+#[test]
+fn canonical_minified() {
+    let canonical = |text: &str| is_canonical_minified(text.as_bytes()).unwrap();
+
+    assert!(canonical(r#"{"a":[1,-2,0.5,{"x":null,"y":true}],"b":{},"c":"a \" b"}"#));
+    assert!(canonical("-0.25"));
+    assert!(canonical("0"));
+
+    assert!(!canonical(r#"{"a":1 }"#));
+    assert!(!canonical(r#"{"a": 1}"#));
+    assert!(!canonical("[1,2]\n"));
+    assert!(!canonical(r#"{"b":1,"a":2}"#));
+    assert!(!canonical(r#"{"a":{"d":1,"c":2}}"#));
+    assert!(!canonical("[1.50]"));
+    assert!(!canonical("[1E2]"));
+    assert!(!canonical("-0"));
+    assert!(!canonical("-0.0"));
+
+    // The keys of different objects are not compared.
+    assert!(canonical(r#"[{"b":1},{"a":2}]"#));
+    assert!(canonical(r#"{"b":{"z":1},"c":2}"#));
+
+    assert!(is_canonical_minified(b"[1,").is_err());
+}
+
+#[test]
+fn stats_strings_needing_escape() {
+    let (_, stats) = validate_with_stats(r#"["ascii", "café"]"#.as_bytes()).unwrap();
+    assert_eq!(stats.strings_needing_escape_for_ascii, 1);
+
+    // The unicode escapes are already ASCII.
+    let text = r#"{"clé": ["\u00e9", "😜", "a", "日本"], "b": "é"}"#;
+    let (_, stats) = validate_with_stats(text.as_bytes()).unwrap();
+    assert_eq!(stats.strings_needing_escape_for_ascii, 4);
+}
+
+#[test]
+fn max_nesting_weight() {
+    // The array is at depth 0, the objects at depth 1 and the numbers at depth 2.
+    let text = r#"[{"a": 1}, {"a": 2}, {"a": 3}]"#;
+    assert_eq!(parse_with(JsonChecker::new(()).max_nesting_weight(9), text.as_bytes()), Ok(JsonType::Array));
+    assert_eq!(parse_with(JsonChecker::new(()).max_nesting_weight(8), text.as_bytes()), Err(Error::NestingWeightExceeded));
+
+    // A wide document trips the limit, even if it is not deep.
+    let text = format!("[{}]", vec![r#"{"a": [1]}"#; 100].join(","));
+    assert_eq!(parse_with(JsonChecker::new(()).max_nesting_weight(500), text.as_bytes()), Err(Error::NestingWeightExceeded));
+    assert_eq!(parse_with(JsonChecker::new(()).max_nesting_weight(600), text.as_bytes()), Ok(JsonType::Array));
+    assert_eq!(parse_with(JsonChecker::new(()).max_nesting_weight(0), b"42"), Ok(JsonType::Number));
+}
+
+#[test]
+fn flatten_leaves() {
+    let mut leaves = Vec::new();
+    let text = r#"{"a":{"b":1},"c":[2,3]}"#;
+    let json_type = flatten(text.as_bytes(), |pointer, bytes, json_type| {
+        leaves.push((pointer, String::from_utf8(bytes.to_vec()).unwrap(), json_type));
+    }).unwrap();
+
+    assert_eq!(json_type, JsonType::Object);
+    assert_eq!(leaves, vec![
+        ("/a/b".to_string(), "1".to_string(), JsonType::Number),
+        ("/c/0".to_string(), "2".to_string(), JsonType::Number),
+        ("/c/1".to_string(), "3".to_string(), JsonType::Number),
+    ]);
+
+    let mut pointers = Vec::new();
+    let text = r#"[{}, [[]], {"x": [null, {"y": "z"}]}, true]"#;
+    flatten(text.as_bytes(), |pointer, _, _| pointers.push(pointer)).unwrap();
+    assert_eq!(pointers, vec!["/2/x/0", "/2/x/1/y", "/3"]);
+
+    assert!(flatten("[1, 2".as_bytes(), |_, _, _| ()).is_err());
+}
+
+#[test]
+fn controllable_visitor() {
+    use std::ops::ControlFlow;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        break_on_key: bool,
+    }
+
+    impl Visitor for Recorder {
+        fn start_object(&mut self) -> ControlFlow<()> {
+            self.events.push("{".to_string());
+            ControlFlow::Continue(())
+        }
+
+        fn end_object(&mut self) -> ControlFlow<()> {
+            self.events.push("}".to_string());
+            ControlFlow::Continue(())
+        }
+
+        fn key(&mut self, key: &[u8]) -> ControlFlow<()> {
+            self.events.push(String::from_utf8(key.to_vec()).unwrap());
+            if self.break_on_key { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        }
+
+        fn scalar(&mut self, _: JsonType, bytes: &[u8]) -> ControlFlow<()> {
+            self.events.push(String::from_utf8(bytes.to_vec()).unwrap());
+            ControlFlow::Continue(())
+        }
+    }
+
+    // The rest of the document is neither visited nor validated.
+    let mut visitor = Recorder { break_on_key: true, ..Recorder::default() };
+    let outcome = validate_with_controllable_visitor(r#"{"a": 1, "b": x"#.as_bytes(), &mut visitor).unwrap();
+    assert_eq!(outcome, ValidationOutcome::Stopped);
+    assert_eq!(visitor.events, vec!["{", r#""a""#]);
+
+    let mut visitor = Recorder::default();
+    let outcome = validate_with_controllable_visitor(r#"{"a": 1, "b": 2}"#.as_bytes(), &mut visitor).unwrap();
+    assert_eq!(outcome, ValidationOutcome::Completed(JsonType::Object));
+    assert_eq!(visitor.events, vec!["{", r#""a""#, "1", r#""b""#, "2", "}"]);
+
+    assert!(validate_with_controllable_visitor(r#"{"a": 1, "b": x"#.as_bytes(), Recorder::default()).is_err());
+}
+
+#[test]
+fn merkle_tree_subtrees() {
+    let a = merkle_tree(&br#"{"x": {"y": [1, {"z": true}]}, "w": [{"v": null}]}"#[..]).unwrap();
+    let b = merkle_tree(&br#"{"x": {"y": [1, {"z": false}]}, "w": [ {"v":null} ]}"#[..]).unwrap();
+
+    assert_ne!(a.root, b.root);
+    let pointers: Vec<_> = a.subtrees.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(pointers, vec!["/x/y/1", "/x/y", "/x", "/w/0", "/w", ""]);
+    assert_eq!(a.root, a.subtrees[5].1);
+
+    // The unmodified branches share their hashes, the others don't.
+    for (i, ((pa, ha), (pb, hb))) in a.subtrees.iter().zip(&b.subtrees).enumerate() {
+        assert_eq!(pa, pb);
+        match i {
+            0..=2 | 5 => assert_ne!(ha, hb, "{}", pa),
+            _ => assert_eq!(ha, hb, "{}", pa),
+        }
+    }
+
+    let a = merkle_tree(&b"12"[..]).unwrap();
+    let b = merkle_tree(&b" 12 "[..]).unwrap();
+    assert_eq!(a, b);
+    assert!(a.subtrees.is_empty());
+    assert_ne!(a.root, merkle_tree(&b"13"[..]).unwrap().root);
+}
+
+#[test]
+fn stats_type_by_depth() {
+    let text = r#"{"a": [1, 2, "x"], "b": {"c": null, "d": "y", "e": "z"}, "f": true}"#;
+    let (_, stats) = validate_with_stats(text.as_bytes()).unwrap();
+
+    assert_eq!(stats.type_by_depth.len(), 3);
+    assert_eq!(stats.type_by_depth[0], [0, 0, 0, 0, 0, 1]);
+    assert_eq!(stats.type_by_depth[1][JsonType::Array as usize], 1);
+    assert_eq!(stats.type_by_depth[1][JsonType::Object as usize], 1);
+    assert_eq!(stats.type_by_depth[1][JsonType::Bool as usize], 1);
+    assert_eq!(stats.type_by_depth[2][JsonType::Number as usize], 2);
+    assert_eq!(stats.type_by_depth[2][JsonType::String as usize], 3);
+    assert_eq!(stats.type_by_depth[2][JsonType::Null as usize], 1);
+
+    let (_, stats) = validate_with_stats("42".as_bytes()).unwrap();
+    assert_eq!(stats.type_by_depth, vec![[0, 0, 1, 0, 0, 0]]);
+}
@@ -0,0 +1,124 @@
+use std::io;
+use std::ops::ControlFlow;
+
+use crate::events::{try_scan_reader, Event, Scanner};
+use crate::JsonType;
+
+/// The callbacks called by `validate_with_controllable_visitor` for every event
+/// of the document, returning `ControlFlow::Break` stops the validation.
+///
+/// The keys and scalars are given with their raw bytes, quotes and escapes included.
+/// All of the methods do nothing and continue by default.
+pub trait Visitor {
+    /// Called when an object opens.
+    fn start_object(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called when an object closes.
+    fn end_object(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called when an array opens.
+    fn start_array(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called when an array closes.
+    fn end_array(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called for every key of the objects.
+    fn key(&mut self, _key: &[u8]) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called for every value that is not an array or an object.
+    fn scalar(&mut self, _json_type: JsonType, _bytes: &[u8]) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+impl<V: Visitor + ?Sized> Visitor for &mut V {
+    fn start_object(&mut self) -> ControlFlow<()> {
+        (**self).start_object()
+    }
+
+    fn end_object(&mut self) -> ControlFlow<()> {
+        (**self).end_object()
+    }
+
+    fn start_array(&mut self) -> ControlFlow<()> {
+        (**self).start_array()
+    }
+
+    fn end_array(&mut self) -> ControlFlow<()> {
+        (**self).end_array()
+    }
+
+    fn key(&mut self, key: &[u8]) -> ControlFlow<()> {
+        (**self).key(key)
+    }
+
+    fn scalar(&mut self, json_type: JsonType, bytes: &[u8]) -> ControlFlow<()> {
+        (**self).scalar(json_type, bytes)
+    }
+}
+
+/// The result of the `validate_with_controllable_visitor` function.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValidationOutcome {
+    /// The whole document has been validated.
+    Completed(JsonType),
+    /// The visitor stopped the validation, the rest of the document has not been read.
+    Stopped,
+}
+
+/// Validate the JSON from the reader and call the visitor for every event,
+/// the visitor can stop the validation without it being an error.
+///
+/// # Example
+///
+/// ```
+/// # fn fmain() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::ops::ControlFlow;
+/// use oxidized_json_checker::{validate_with_controllable_visitor, ValidationOutcome, Visitor};
+///
+/// #[derive(Default)]
+/// struct FindKey { found: bool }
+///
+/// impl Visitor for FindKey {
+///     fn key(&mut self, key: &[u8]) -> ControlFlow<()> {
+///         self.found = key == br#""id""#;
+///         if self.found { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+///     }
+/// }
+///
+/// let text = r#"{"name": "kero", "id": 27, "this": is not validated"#;
+/// let mut visitor = FindKey::default();
+/// let outcome = validate_with_controllable_visitor(text.as_bytes(), &mut visitor)?;
+/// assert_eq!(outcome, ValidationOutcome::Stopped);
+/// assert!(visitor.found);
+/// # Ok(()) }
+/// # fmain().unwrap()
+/// ```
+pub fn validate_with_controllable_visitor<R, V>(reader: R, mut visitor: V) -> io::Result<ValidationOutcome>
+where
+    R: io::Read,
+    V: Visitor,
+{
+    let outcome = try_scan_reader(reader, Scanner::new(true), |token| {
+        match token.event {
+            Event::StartObject => visitor.start_object(),
+            Event::EndObject => visitor.end_object(),
+            Event::StartArray => visitor.start_array(),
+            Event::EndArray => visitor.end_array(),
+            Event::Key => visitor.key(&token.bytes),
+            Event::Scalar(json_type) => visitor.scalar(json_type, &token.bytes),
+        }
+    })?;
+
+    Ok(outcome.map_or(ValidationOutcome::Stopped, ValidationOutcome::Completed))
+}